@@ -59,6 +59,59 @@ where
     });
 }
 
+// Sampling the matrix from the seed, happens on public key decoding
+fn matrix_bench<const DIM: usize, const N: usize>(c: &mut Criterion)
+where
+    Dim<DIM>: Config<32>,
+{
+    let (_, pk) = kem::key_pair::<DIM>(rand::random());
+    let pk = pk.to_array::<N>();
+    c.bench_function(&format!("Matrix Generation {}", DIM * 256), |b| {
+        b.iter(|| black_box(kem::PublicKey::<DIM>::from_bytes(&pk)))
+    });
+}
+
+// Decapsulating a single ciphertext with a batch of keys
+fn decap_batch_bench<const DIM: usize>(c: &mut Criterion)
+where
+    Dim<DIM>: Config<32>,
+{
+    let key_pairs = (0..16)
+        .map(|_| kem::key_pair::<DIM>(rand::random()))
+        .collect::<Vec<_>>();
+    let ct = kem::CipherText::<DIM>::from_bytes(&hex::decode(CT_HEX).unwrap());
+    c.bench_function(&format!("Decapsulate Batch {}", DIM * 256), |b| {
+        b.iter(|| {
+            for (sk, pk) in &key_pairs {
+                black_box(kem::decapsulate(sk, pk, &ct));
+            }
+        })
+    });
+    c.bench_function(&format!("Decapsulate Batch Prepared {}", DIM * 256), |b| {
+        b.iter(|| {
+            let prepared = ct.prepared();
+            for (sk, pk) in &key_pairs {
+                black_box(kem::decapsulate_prepared(sk, pk, &prepared));
+            }
+        })
+    });
+}
+
+// Decapsulating many ciphertexts under one key, on the thread pool with the `rayon` feature
+fn decap_many_bench<const DIM: usize>(c: &mut Criterion)
+where
+    Dim<DIM>: Config<32>,
+{
+    let (sk, pk) = kem::key_pair::<DIM>(rand::random());
+    let cts = (0..64)
+        .map(|_| kem::encapsulate(rand::random(), &pk).0)
+        .collect::<Vec<_>>();
+    let mut shared_secrets = vec![[0; 32]; cts.len()];
+    c.bench_function(&format!("Decapsulate Many {}", DIM * 256), |b| {
+        b.iter(|| kem::decapsulate_many(&sk, &pk, &cts, black_box(&mut shared_secrets)))
+    });
+}
+
 criterion_group!(
     benches,
     keypair_bench::<2>,
@@ -67,5 +120,8 @@ criterion_group!(
     encap_bench::<3>,
     decap_bench::<3>,
     decap_fail_bench::<3>,
+    decap_batch_bench::<3>,
+    decap_many_bench::<3>,
+    matrix_bench::<4, { kem::Kyber1024::PUBLIC_KEY_BYTES }>,
 );
 criterion_main!(benches);
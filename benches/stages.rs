@@ -0,0 +1,55 @@
+use criterion::{criterion_group, criterion_main, Criterion, black_box};
+use vru_kyber::bench;
+
+// Sampling a single element of the matrix
+fn uniform_bench(c: &mut Criterion) {
+    let seed = rand::random();
+    c.bench_function("Uniform", |b| {
+        b.iter(|| black_box(bench::uniform(&seed, 1, 2)))
+    });
+}
+
+// Sampling the whole matrix
+fn matrix_bench<const DIM: usize>(c: &mut Criterion) {
+    let seed = rand::random();
+    c.bench_function(&format!("Matrix {}", DIM * 256), |b| {
+        b.iter(|| black_box(bench::matrix::<DIM>(&seed)))
+    });
+}
+
+// Sampling a single noise polynomial, `eta1` of Kyber768
+fn noise_bench(c: &mut Criterion) {
+    let seed = rand::random();
+    c.bench_function("Noise", |b| {
+        b.iter(|| black_box(bench::noise::<3>(&seed, 1)))
+    });
+}
+
+fn ntt_bench(c: &mut Criterion) {
+    let p = bench::noise::<3>(&rand::random(), 0);
+    c.bench_function("NTT", |b| b.iter(|| black_box(bench::ntt(black_box(p)))));
+    let p = bench::uniform(&rand::random(), 0, 0);
+    c.bench_function("Inverse NTT", |b| {
+        b.iter(|| black_box(bench::inverse_ntt(black_box(p))))
+    });
+}
+
+// The inner product of the vectors of Kyber768
+fn mul_fold_bench(c: &mut Criterion) {
+    let seed = rand::random();
+    let a = [0, 1, 2].map(|j| bench::uniform(&seed, 0, j));
+    let b = [0, 1, 2].map(|j| bench::uniform(&seed, 1, j));
+    c.bench_function("Multiply Fold 768", |bencher| {
+        bencher.iter(|| black_box(bench::mul_fold_montgomery(&a, &b)))
+    });
+}
+
+criterion_group!(
+    benches,
+    uniform_bench,
+    matrix_bench::<3>,
+    noise_bench,
+    ntt_bench,
+    mul_fold_bench,
+);
+criterion_main!(benches);
@@ -0,0 +1,9 @@
+#!/usr/bin/env bash
+
+# the crate and its dependencies build for a target without `std` and without `alloc`,
+# `rustup target add thumbv7em-none-eabihf` first
+set -e
+
+cargo build --target thumbv7em-none-eabihf
+cargo build --target thumbv7em-none-eabihf --features "compact-public-key serde-secret kyber90s hybrid unstable-cpa noise-analysis"
+cargo test --test no_alloc
@@ -0,0 +1,27 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vru_kyber::{
+    config::{Dim, Config},
+    kem::CipherText,
+};
+
+// never panics, any bytes of the right length are a cipher text
+// and serialize back to the same bytes
+fn check<const DIM: usize>(data: &[u8])
+where
+    Dim<DIM>: Config<32>,
+{
+    let result = CipherText::<DIM>::try_from_bytes(data);
+    assert_eq!(result.is_ok(), data.len() == CipherText::<DIM>::BYTES);
+
+    if let Ok(ct) = result {
+        assert!(ct.byte_iter().eq(data.iter().copied()));
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    check::<2>(data);
+    check::<3>(data);
+    check::<4>(data);
+});
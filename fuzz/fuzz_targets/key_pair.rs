@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vru_kyber::kem::try_load_key_pair;
+
+// never panics, a loaded key pair serializes back to the same bytes
+// `N` is `SecretKey::<DIM>::BYTES`
+fn check<const DIM: usize, const N: usize>(data: &[u8]) {
+    if let Ok((sk, pk)) = try_load_key_pair::<DIM>(data) {
+        assert_eq!(sk.to_array::<N>(&pk)[..], *data);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    check::<2, 1632>(data);
+    check::<3, 2400>(data);
+    check::<4, 3168>(data);
+});
@@ -0,0 +1,25 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sha3::digest::Update;
+use vru_kyber::kem::{PublicKey, PublicKeyBuilder};
+
+// never panics, a decoded key serializes back to the same bytes,
+// the streaming decoder agrees with the slice decoder
+fn check<const DIM: usize>(data: &[u8]) {
+    let result = PublicKey::<DIM>::try_from_bytes(data);
+
+    let mut builder = PublicKeyBuilder::<DIM>::new();
+    builder.update(data);
+    assert_eq!(builder.finish().ok(), result.clone().ok());
+
+    if let Ok(pk) = result {
+        assert!(pk.byte_iter().eq(data.iter().copied()));
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    check::<2>(data);
+    check::<3>(data);
+    check::<4>(data);
+});
@@ -5,6 +5,7 @@ use core::{
 };
 
 #[derive(Clone, Copy)]
+#[repr(transparent)]
 pub struct Array<T, const N: usize>([MaybeUninit<T>; N])
 where
     T: Copy;
@@ -14,24 +15,63 @@ where
     T: Copy,
 {
     #[inline]
-    pub fn initialize(array: [MaybeUninit<T>; N]) -> Self {
+    pub const fn initialize(array: [MaybeUninit<T>; N]) -> Self {
         Array(array)
     }
 
+    /// Takes at most `N` items from the iterator. If the iterator is too short,
+    /// the rest of the items are zero bits, the array only holds plain integers.
     #[inline]
     pub fn new<I>(it: &mut I) -> Self
     where
         I: Iterator<Item = T>,
     {
-        let mut s = Array(unsafe { MaybeUninit::uninit().assume_init() });
-        let mut c = N;
-        for (i, x) in it.enumerate().take(N) {
-            s.0[i] = MaybeUninit::new(x);
-            c -= 1;
+        let mut s = Array([MaybeUninit::zeroed(); N]);
+        for (item, x) in s.0.iter_mut().zip(it) {
+            *item = MaybeUninit::new(x);
         }
-        assert_eq!(c, 0, "iterator is too short to initialize {N} items");
         s
     }
+
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_ref().iter()
+    }
+
+    #[inline]
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.as_mut().iter_mut()
+    }
+
+    /// The items `f(0), ..., f(N - 1)`, computed in parallel.
+    #[cfg(feature = "rayon")]
+    #[inline]
+    pub fn par_new<F>(f: F) -> Self
+    where
+        T: Send,
+        F: Fn(usize) -> T + Sync + Send,
+    {
+        use rayon::iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+
+        let mut s = Array([MaybeUninit::zeroed(); N]);
+        s.0.par_iter_mut()
+            .enumerate()
+            .for_each(|(i, item)| *item = MaybeUninit::new(f(i)));
+        s
+    }
+
+    /// The same as `new`, but stops on the first error.
+    #[inline]
+    pub fn try_new<I, E>(it: &mut I) -> Result<Self, E>
+    where
+        I: Iterator<Item = Result<T, E>>,
+    {
+        let mut s = Array([MaybeUninit::zeroed(); N]);
+        for (item, x) in s.0.iter_mut().zip(it) {
+            *item = MaybeUninit::new(x?);
+        }
+        Ok(s)
+    }
 }
 
 impl<T, const N: usize> FromIterator<T> for Array<T, N>
@@ -47,6 +87,78 @@ where
     }
 }
 
+pub struct IntoIter<T, const N: usize>
+where
+    T: Copy,
+{
+    array: Array<T, N>,
+    position: usize,
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N>
+where
+    T: Copy,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.array.as_ref().get(self.position).copied();
+        self.position += usize::from(item.is_some());
+        item
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = N - self.position;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> where T: Copy {}
+
+impl<T, const N: usize> IntoIterator for Array<T, N>
+where
+    T: Copy,
+{
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            array: self,
+            position: 0,
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a Array<T, N>
+where
+    T: Copy,
+{
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut Array<T, N>
+where
+    T: Copy,
+{
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 impl<T, const N: usize> AsRef<[T]> for Array<T, N>
 where
     T: Copy,
@@ -108,3 +220,44 @@ where
 }
 
 impl<T, const N: usize> Eq for Array<T, N> where T: Copy + PartialEq {}
+
+#[cfg(test)]
+mod tests {
+    use super::Array;
+
+    #[test]
+    fn short_iterator() {
+        let a = Array::<i16, 4>::new(&mut [1, 2].into_iter());
+        assert_eq!(a.as_ref(), [1, 2, 0, 0]);
+
+        let a = Array::<i16, 4>::try_new(&mut [Ok::<_, ()>(1), Err(())].into_iter());
+        assert!(a.is_err());
+
+        let a = Array::<i16, 4>::try_new(&mut [Ok::<_, ()>(1), Ok(2)].into_iter());
+        assert_eq!(a.unwrap().as_ref(), [1, 2, 0, 0]);
+    }
+
+    #[test]
+    fn long_iterator() {
+        let mut it = 0..6;
+        let a = Array::<i16, 4>::new(&mut it);
+        assert_eq!(a.as_ref(), [0, 1, 2, 3]);
+        assert_eq!(it.next(), Some(4));
+    }
+
+    #[test]
+    fn iterators() {
+        let mut a = Array::<i16, 4>::new(&mut (1..5));
+        for x in &mut a {
+            *x *= 2;
+        }
+        assert_eq!((&a).into_iter().sum::<i16>(), 20);
+        let mut it = a.into_iter();
+        assert_eq!(it.len(), 4);
+        assert_eq!(
+            it.by_ref().collect::<Array<i16, 4>>().as_ref(),
+            [2, 4, 6, 8]
+        );
+        assert_eq!(it.next(), None);
+    }
+}
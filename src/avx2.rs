@@ -0,0 +1,304 @@
+//! AVX2 implementation of the forward and inverse NTT and of the rejection sampling.
+//! Computes exactly the same integers as the scalar code in `poly.rs`,
+//! including the intermediate Barrett reductions.
+
+use core::arch::x86_64::{
+    __m256i, _mm256_add_epi16, _mm256_and_si256, _mm256_blend_epi16, _mm256_blend_epi32,
+    _mm256_castsi256_si128, _mm256_cmpgt_epi16, _mm256_extracti128_si256, _mm256_loadu_si256,
+    _mm256_movemask_epi8, _mm256_mulhi_epi16, _mm256_mullo_epi16, _mm256_permute2x128_si256,
+    _mm256_permute4x64_epi64, _mm256_set1_epi16, _mm256_setr_epi8, _mm256_shuffle_epi8,
+    _mm256_slli_epi64, _mm256_srai_epi16, _mm256_srli_epi16, _mm256_srli_epi64,
+    _mm256_storeu_si256, _mm256_sub_epi16, _mm256_unpackhi_epi64, _mm256_unpacklo_epi64,
+    _mm_loadu_si128, _mm_shuffle_epi8, _mm_storeu_si128,
+};
+
+use super::{coefficient::Coefficient, poly::ZETAS};
+
+cpufeatures::new!(avx2_cpuid, "avx2");
+
+#[inline]
+pub fn available() -> bool {
+    avx2_cpuid::get()
+}
+
+// `Coefficient::Q_INV` truncated to 16 bits
+const Q_INV: i16 = 62209_u16 as i16;
+
+const BARRETT_V: i16 = ((1 << 26) / Coefficient::Q as i32 + 1) as i16;
+
+// the twiddles for the last three layers, where a butterfly does not span a whole register,
+// `TABLE[layer][chunk]` is the vector of twiddles for the chunk of 32 coefficients,
+// the lanes are in the order the shuffles put the coefficients
+const fn table(inverse: bool) -> [[[i16; 16]; 8]; 3] {
+    let mut t = [[[0; 16]; 8]; 3];
+    let mut chunk = 0;
+    while chunk < 8 {
+        let mut lane = 0;
+        while lane < 16 {
+            // the index of the group of butterflies within the chunk
+            let groups = [
+                lane / 8,
+                [0, 2, 1, 3][lane / 4],
+                if lane % 4 < 2 { lane / 4 } else { 4 + lane / 4 },
+            ];
+            let mut layer = 0;
+            while layer < 3 {
+                // layers of length 8, 4 and 2 have 2, 4 and 8 groups in the chunk
+                let per_chunk = 2 << layer;
+                let g = chunk * per_chunk + groups[layer];
+                let k = if inverse {
+                    16 * per_chunk - 1 - g
+                } else {
+                    8 * per_chunk + g
+                };
+                t[layer][chunk][lane] = ZETAS[k];
+                layer += 1;
+            }
+            lane += 1;
+        }
+        chunk += 1;
+    }
+    t
+}
+
+const FORWARD: [[[i16; 16]; 8]; 3] = table(false);
+
+const INVERSE: [[[i16; 16]; 8]; 3] = table(true);
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn montgomery_mul(a: __m256i, zeta: __m256i) -> __m256i {
+    let zeta_q_inv = _mm256_mullo_epi16(zeta, _mm256_set1_epi16(Q_INV));
+    let u = _mm256_mullo_epi16(a, zeta_q_inv);
+    let hi = _mm256_mulhi_epi16(a, zeta);
+    let t = _mm256_mulhi_epi16(u, _mm256_set1_epi16(Coefficient::Q));
+    _mm256_sub_epi16(hi, t)
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn barrett_reduce(a: __m256i) -> __m256i {
+    let t = _mm256_mulhi_epi16(a, _mm256_set1_epi16(BARRETT_V));
+    let t = _mm256_srai_epi16::<10>(_mm256_add_epi16(t, _mm256_set1_epi16(1 << 9)));
+    _mm256_sub_epi16(a, _mm256_mullo_epi16(t, _mm256_set1_epi16(Coefficient::Q)))
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn forward_butterfly(a: __m256i, b: __m256i, zeta: __m256i) -> (__m256i, __m256i) {
+    let t = montgomery_mul(b, zeta);
+    (
+        barrett_reduce(_mm256_add_epi16(a, t)),
+        barrett_reduce(_mm256_sub_epi16(a, t)),
+    )
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn inverse_butterfly(a: __m256i, b: __m256i, zeta: __m256i) -> (__m256i, __m256i) {
+    let s = barrett_reduce(_mm256_add_epi16(a, b));
+    let d = barrett_reduce(_mm256_sub_epi16(b, a));
+    (s, montgomery_mul(d, zeta))
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn load(r: &[i16; 256], i: usize) -> __m256i {
+    _mm256_loadu_si256(r[i..(i + 16)].as_ptr().cast())
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn store(r: &mut [i16; 256], i: usize, v: __m256i) {
+    _mm256_storeu_si256(r[i..(i + 16)].as_mut_ptr().cast(), v);
+}
+
+// splits two registers into the first and second halves of the butterflies of the layer
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn split(layer: usize, x: __m256i, y: __m256i) -> (__m256i, __m256i) {
+    match layer {
+        0 => (
+            _mm256_permute2x128_si256::<0x20>(x, y),
+            _mm256_permute2x128_si256::<0x31>(x, y),
+        ),
+        1 => (_mm256_unpacklo_epi64(x, y), _mm256_unpackhi_epi64(x, y)),
+        _ => (
+            _mm256_blend_epi32::<0xaa>(x, _mm256_slli_epi64::<32>(y)),
+            _mm256_blend_epi32::<0xaa>(_mm256_srli_epi64::<32>(x), y),
+        ),
+    }
+}
+
+// inverse of `split`, the permutations are involutions
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn join(layer: usize, a: __m256i, b: __m256i) -> (__m256i, __m256i) {
+    split(layer, a, b)
+}
+
+/// # Safety
+///
+/// the CPU must support AVX2, see `available`
+#[target_feature(enable = "avx2")]
+pub unsafe fn ntt(r: &mut [i16; 256]) {
+    let mut k = 1;
+    let mut len = 128;
+    while len >= 16 {
+        let mut start = 0;
+        while start < 256 {
+            let zeta = _mm256_set1_epi16(ZETAS[k]);
+            k += 1;
+            let mut j = start;
+            while j < start + len {
+                let (a, b) = forward_butterfly(load(r, j), load(r, j + len), zeta);
+                store(r, j, a);
+                store(r, j + len, b);
+                j += 16;
+            }
+            start = j + len;
+        }
+        len >>= 1;
+    }
+
+    for chunk in 0..8 {
+        let mut x = load(r, chunk * 32);
+        let mut y = load(r, chunk * 32 + 16);
+        for (layer, zetas) in FORWARD.iter().enumerate() {
+            let zeta = _mm256_loadu_si256(zetas[chunk].as_ptr().cast());
+            let (a, b) = split(layer, x, y);
+            let (a, b) = forward_butterfly(a, b, zeta);
+            let (a, b) = join(layer, a, b);
+            x = a;
+            y = b;
+        }
+        store(r, chunk * 32, barrett_reduce(x));
+        store(r, chunk * 32 + 16, barrett_reduce(y));
+    }
+}
+
+/// # Safety
+///
+/// the CPU must support AVX2, see `available`
+#[target_feature(enable = "avx2")]
+pub unsafe fn inverse_ntt(r: &mut [i16; 256]) {
+    for chunk in 0..8 {
+        let mut x = load(r, chunk * 32);
+        let mut y = load(r, chunk * 32 + 16);
+        for (layer, zetas) in INVERSE.iter().enumerate().rev() {
+            let zeta = _mm256_loadu_si256(zetas[chunk].as_ptr().cast());
+            let (a, b) = split(layer, x, y);
+            let (a, b) = inverse_butterfly(a, b, zeta);
+            let (a, b) = join(layer, a, b);
+            x = a;
+            y = b;
+        }
+        store(r, chunk * 32, x);
+        store(r, chunk * 32 + 16, y);
+    }
+
+    let mut k = 15;
+    let mut len = 16;
+    while len <= 128 {
+        let mut start = 0;
+        while start < 256 {
+            let zeta = _mm256_set1_epi16(ZETAS[k]);
+            k -= 1;
+            let mut j = start;
+            while j < start + len {
+                let (a, b) = inverse_butterfly(load(r, j), load(r, j + len), zeta);
+                store(r, j, a);
+                store(r, j + len, b);
+                j += 16;
+            }
+            start = j + len;
+        }
+        len <<= 1;
+    }
+
+    let f = _mm256_set1_epi16(Coefficient::F.0);
+    for i in (0..256).step_by(16) {
+        store(r, i, montgomery_mul(load(r, i), f));
+    }
+}
+
+/// The bytes of the stream `rej_uniform` takes at once, three blocks of SHAKE128.
+pub const REJ_BYTES: usize = 3 * 168;
+
+// `COMPACT[mask]` moves the 16 bit lanes selected by the `mask` to the front
+const fn compact() -> [[u8; 16]; 256] {
+    let mut t = [[0xff; 16]; 256];
+    let mut mask = 0;
+    while mask < 256 {
+        let mut n = 0;
+        let mut lane = 0;
+        while lane < 8 {
+            if mask & (1 << lane) != 0 {
+                t[mask][2 * n] = 2 * lane as u8;
+                t[mask][2 * n + 1] = 2 * lane as u8 + 1;
+                n += 1;
+            }
+            lane += 1;
+        }
+        mask += 1;
+    }
+    t
+}
+
+const COMPACT: [[u8; 16]; 256] = compact();
+
+// takes every second bit of the mask of bytes, the mask of 16 bit lanes
+#[inline]
+const fn lanes(mask: u32) -> u32 {
+    let mut x = mask & 0x5555_5555;
+    x = (x | (x >> 1)) & 0x3333_3333;
+    x = (x | (x >> 2)) & 0x0f0f_0f0f;
+    x = (x | (x >> 4)) & 0x00ff_00ff;
+    (x | (x >> 8)) & 0x0000_ffff
+}
+
+/// Writes the candidates of `buf` less than `q` to `out`, in order. Takes 24 bytes,
+/// 16 candidates, at once while `out` has room for all of them. Returns the number
+/// of the written coefficients and of the consumed bytes, the caller continues from there.
+///
+/// # Safety
+///
+/// the CPU must support AVX2, see `available`
+#[target_feature(enable = "avx2")]
+pub unsafe fn rej_uniform(out: &mut [i16], buf: &[u8; REJ_BYTES + 8]) -> (usize, usize) {
+    // the low half takes the bytes `0..12`, the high half takes the bytes `12..24`,
+    // each 16 bit lane gets the two bytes its 12 bit candidate lies in
+    let idx = _mm256_setr_epi8(
+        0, 1, 1, 2, 3, 4, 4, 5, 6, 7, 7, 8, 9, 10, 10, 11, 4, 5, 5, 6, 7, 8, 8, 9, 10, 11, 11, 12,
+        13, 14, 14, 15,
+    );
+    let mask = _mm256_set1_epi16(0xfff);
+    let bound = _mm256_set1_epi16(Coefficient::Q);
+
+    let mut ctr = 0;
+    let mut pos = 0;
+    while ctr + 16 <= out.len() && pos + 24 <= REJ_BYTES {
+        // the load takes 32 bytes, the padding of the buffer keeps it in bounds
+        let f = _mm256_loadu_si256(buf[pos..(pos + 32)].as_ptr().cast());
+        let f = _mm256_permute4x64_epi64::<0x94>(f);
+        let f = _mm256_shuffle_epi8(f, idx);
+        let f = _mm256_blend_epi16::<0xaa>(f, _mm256_srli_epi16::<4>(f));
+        let f = _mm256_and_si256(f, mask);
+        pos += 24;
+
+        let good = lanes(_mm256_movemask_epi8(_mm256_cmpgt_epi16(bound, f)) as u32);
+        let halves = [_mm256_castsi256_si128(f), _mm256_extracti128_si256::<1>(f)];
+        for (i, half) in halves.into_iter().enumerate() {
+            let m = ((good >> (8 * i)) & 0xff) as usize;
+            let shuffle = _mm_loadu_si128(COMPACT[m].as_ptr().cast());
+            // writes all 8 lanes, the ones past the accepted are overwritten later
+            _mm_storeu_si128(
+                out[ctr..(ctr + 8)].as_mut_ptr().cast(),
+                _mm_shuffle_epi8(half, shuffle),
+            );
+            ctr += m.count_ones() as usize;
+        }
+    }
+
+    (ctr, pos)
+}
@@ -0,0 +1,121 @@
+//! NOT A STABLE API, the internal stages exposed for `benches/stages.rs`.
+//! May change in any release.
+
+use super::{
+    array::Array,
+    config::{Dim, Config},
+    indcpa, kem,
+    poly::{self, Ntt, PolyMul},
+    symmetric::Shake,
+};
+
+/// The polynomial in the normal domain.
+#[derive(Clone, Copy)]
+pub struct Poly(poly::Poly<32, true>);
+
+/// The polynomial in the NTT domain.
+#[derive(Clone, Copy)]
+pub struct NttPoly(poly::Poly<32, false>);
+
+impl Poly {
+    /// The coefficients, the canonical representatives.
+    #[must_use]
+    pub fn coefficients(&self) -> [u16; 256] {
+        let mut r = [0; 256];
+        r.iter_mut()
+            .enumerate()
+            .for_each(|(i, c)| *c = self.0[i].pack());
+        r
+    }
+}
+
+impl NttPoly {
+    /// The coefficients in the order of the implementation, the canonical representatives.
+    #[must_use]
+    pub fn coefficients(&self) -> [u16; 256] {
+        let mut r = [0; 256];
+        r.iter_mut()
+            .enumerate()
+            .for_each(|(i, c)| *c = self.0[i].pack());
+        r
+    }
+}
+
+/// The matrix `A`, the rows in the NTT domain.
+#[derive(Clone, Copy)]
+pub struct Matrix<const DIM: usize>(Array<Array<poly::Poly<32, false>, DIM>, DIM>);
+
+/// The element `(i, j)` of the matrix, rejection sampling of SHAKE128.
+#[must_use]
+pub fn uniform(seed: &[u8; 32], i: usize, j: usize) -> NttPoly {
+    NttPoly(poly::Poly::get_uniform::<Shake>(seed, i, j))
+}
+
+/// The whole matrix, see `uniform`.
+#[must_use]
+pub fn matrix<const DIM: usize>(seed: &[u8; 32]) -> Matrix<DIM> {
+    Matrix(indcpa::matrix_from_seed::<DIM, 32, Shake>(seed))
+}
+
+/// The secret or the error polynomial, the centered binomial distribution of the parameter set.
+#[must_use]
+pub fn noise<const DIM: usize>(seed: &[u8; 32], nonce: u8) -> Poly
+where
+    Dim<DIM>: Config<32>,
+{
+    Poly(<Dim<DIM> as Config<32>>::get_noise::<Shake>(seed, nonce))
+}
+
+#[must_use]
+pub fn ntt(p: Poly) -> NttPoly {
+    NttPoly(p.0.ntt())
+}
+
+#[must_use]
+pub fn inverse_ntt(p: NttPoly) -> Poly {
+    Poly(p.0.ntt())
+}
+
+/// The inner product of the vectors in the NTT domain.
+#[must_use]
+pub fn mul_fold_montgomery(a: &[NttPoly], b: &[NttPoly]) -> NttPoly {
+    NttPoly(poly::Poly::mul_fold_montgomery(
+        a.iter().map(|p| &p.0),
+        b.iter().map(|p| &p.0),
+    ))
+}
+
+/// The result of `encapsulate_stages`.
+pub struct Stages<const DIM: usize> {
+    pub cipher_text: kem::CipherText<DIM>,
+    pub shared_secret: [u8; 32],
+    /// The vector `b = A^T r + e1`, before the compression.
+    pub b: [Poly; DIM],
+    /// The polynomial `v = t^T r + e2 + Decompress_1(m)`, before the compression.
+    pub v: Poly,
+}
+
+/// The same as `kem::encapsulate_derand`, and the intermediate values, to diff a failing
+/// test vector against the reference stage by stage.
+#[must_use]
+pub fn encapsulate_stages<const DIM: usize>(
+    message: [u8; 32],
+    public_key: &kem::PublicKey<DIM>,
+) -> Stages<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    let (cipher_text, shared_secret) = kem::encapsulate_derand(message, public_key);
+    let (poly_vector, poly) = cipher_text.inner().parts();
+    let mut b = [Poly(poly::Poly::zero()); DIM];
+    b.iter_mut()
+        .zip(poly_vector.iter())
+        .for_each(|(b, p)| *b = Poly(*p));
+    let v = Poly(*poly);
+    Stages {
+        cipher_text,
+        shared_secret,
+        b,
+        v,
+    }
+}
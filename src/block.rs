@@ -1,8 +1,11 @@
 use core::ops::{Index, IndexMut};
 
-use super::{array::Array, coefficient::Coefficient};
+use subtle::{Choice, ConditionallySelectable};
+
+use super::{array::Array, coefficient::Coefficient, error::DecodeError};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
 pub struct PolyBlock(Array<Coefficient, 8>);
 
 impl PolyBlock {
@@ -15,6 +18,17 @@ impl PolyBlock {
     }
 }
 
+impl ConditionallySelectable for PolyBlock {
+    #[inline]
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        PolyBlock(
+            (0..8)
+                .map(|j| Coefficient::conditional_select(&a.0[j], &b.0[j], choice))
+                .collect(),
+        )
+    }
+}
+
 impl Index<usize> for PolyBlock {
     type Output = Coefficient;
 
@@ -35,6 +49,8 @@ impl PolyBlock {
     #[inline]
     pub fn decompress<const X: u32>(b: &[u8]) -> Self {
         match X {
+            1 => Self::decompress_1(b[0]),
+            3 => Self::decompress_3(b),
             4 => Self::decompress_4(b),
             5 => Self::decompress_5(b),
             10 => Self::decompress_10(b),
@@ -43,6 +59,34 @@ impl PolyBlock {
         }
     }
 
+    // `dv` of round 2 Kyber512 only
+    #[inline]
+    pub fn compress_3(&self) -> [u8; 3] {
+        let t = |j: usize| self.0[j].compress::<3>() as u8;
+        [
+            t(0) | (t(1) << 3) | (t(2) << 6),
+            (t(2) >> 2) | (t(3) << 1) | (t(4) << 4) | (t(5) << 7),
+            (t(5) >> 1) | (t(6) << 2) | (t(7) << 5),
+        ]
+    }
+
+    #[inline]
+    fn decompress_3(b: &[u8]) -> Self {
+        let array = [
+            Coefficient::decompress::<3>(u16::from(b[0])),
+            Coefficient::decompress::<3>(u16::from(b[0] >> 3)),
+            Coefficient::decompress::<3>(u16::from(b[0] >> 6 | b[1] << 2)),
+            Coefficient::decompress::<3>(u16::from(b[1] >> 1)),
+            Coefficient::decompress::<3>(u16::from(b[1] >> 4)),
+            Coefficient::decompress::<3>(u16::from(b[1] >> 7 | b[2] << 1)),
+            Coefficient::decompress::<3>(u16::from(b[2] >> 2)),
+            Coefficient::decompress::<3>(u16::from(b[2] >> 5)),
+        ]
+        .into_iter()
+        .collect();
+        PolyBlock(array)
+    }
+
     #[inline]
     pub fn compress_4(&self) -> [u8; 4] {
         let t = |j: usize| self.0[j].compress::<4>() as u8;
@@ -197,7 +241,7 @@ impl PolyBlock {
     }
 
     #[inline]
-    pub fn from_bytes(b: &[u8]) -> Self {
+    pub fn from_bytes(b: &[u8; 12]) -> Self {
         let array = b
             .chunks(3)
             .flat_map(|b| {
@@ -212,6 +256,28 @@ impl PolyBlock {
         PolyBlock(array)
     }
 
+    #[inline]
+    pub fn from_bytes_checked(b: &[u8; 12]) -> Result<Self, DecodeError> {
+        Self::check_bytes(b)?;
+        Ok(Self::from_bytes(b))
+    }
+
+    /// Checks the 12 bit values are less than `q` without decoding the block,
+    /// the only canonicality check, `from_bytes_checked` calls it.
+    #[inline]
+    pub fn check_bytes(b: &[u8; 12]) -> Result<(), DecodeError> {
+        let canonical = b.chunks(3).all(|b| {
+            let t0 = u16::from(b[0]) | (u16::from(b[1] & 0xf) << 8);
+            let t1 = u16::from(b[1] >> 4) | (u16::from(b[2]) << 4);
+            t0 < Coefficient::Q as u16 && t1 < Coefficient::Q as u16
+        });
+        if canonical {
+            Ok(())
+        } else {
+            Err(DecodeError::NonCanonicalCoefficient)
+        }
+    }
+
     #[inline]
     pub fn mul(&self, rhs: &Self, zetas: [Coefficient; 2]) -> Self {
         use core::mem::MaybeUninit;
@@ -227,43 +293,90 @@ impl PolyBlock {
         ]))
     }
 
-    /// centered binomial distribution
+    /// Centered binomial distribution with `eta = N / 2`, up to `eta = 4`.
+    ///
+    /// The `16 * eta` bits are split in 16 groups of `eta` bits, the coefficient `k` is
+    /// `a - b`, where `a` and `b` are the numbers of ones in the groups `2 * k` and `2 * k + 1`.
+    /// The bits are counted all at once, the `j`-th shift brings the `j`-th bit of each group
+    /// to the lowest position of the group, and the sum fits in the group since `eta < 2^eta`.
     #[inline]
     pub fn cbd<const N: usize>(v: [u8; N]) -> Self {
-        let array = match N {
-            6 => v
-                .as_ref()
-                .chunks(3)
-                .flat_map(|v| {
-                    let mut a = [0; 4];
-                    a[0..3].clone_from_slice(v);
-                    let t = u32::from_le_bytes(a);
-                    let d = (0..3).map(|j| (t >> j) & 0o11_111_111).sum::<u32>();
-
-                    (0..4).map(move |k| {
-                        let mask = (1 << 3) - 1;
-                        let a = (d >> (6 * k)) & mask;
-                        let b = (d >> ((6 * k) + 3)) & mask;
-                        Coefficient::unpack((a as i32 - b as i32) as u16)
-                    })
-                })
-                .collect(),
-            4 => {
-                let t = u32::from_le_bytes(v[..4].try_into().unwrap());
-                let d = (0..2).map(|j| (t >> j) & 0x5555_5555).sum::<u32>();
-
-                (0..8)
-                    .map(|k| {
-                        let mask = (1 << 2) - 1;
-                        let a = (d >> (4 * k)) & mask;
-                        let b = (d >> ((4 * k) + 2)) & mask;
-                        Coefficient::unpack((a as i32 - b as i32) as u16)
-                    })
-                    .collect()
-            }
-            _ => unimplemented!(),
-        };
+        assert!(N % 2 == 0 && N <= 8, "eta must be from 1 to 4");
+        let eta = N / 2;
+
+        let mut bytes = [0; 8];
+        bytes[..N].clone_from_slice(&v);
+        let t = u64::from_le_bytes(bytes);
+
+        // the lowest bit of each group
+        let lowest = (0..16).fold(0u64, |m, i| m | (1 << (eta * i)));
+        let d = (0..eta).map(|j| (t >> j) & lowest).sum::<u64>();
+
+        let mask = (1 << eta) - 1;
+        let array = (0..8)
+            .map(|k| {
+                let a = (d >> (2 * eta * k)) & mask;
+                let b = (d >> (2 * eta * k + eta)) & mask;
+                Coefficient::unpack((a as i32 - b as i32) as u16)
+            })
+            .collect();
 
         PolyBlock(array)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::PolyBlock;
+
+    // bit by bit, the `i`-th bit of the input is the bit `i % 8` of the byte `i / 8`
+    fn reference<const N: usize>(v: [u8; N]) -> [i16; 8] {
+        let eta = N / 2;
+        let bit = |i: usize| i16::from((v[i / 8] >> (i % 8)) & 1);
+        let mut r = [0; 8];
+        for (k, r) in r.iter_mut().enumerate() {
+            let a = (0..eta).map(|j| bit(2 * eta * k + j)).sum::<i16>();
+            let b = (0..eta).map(|j| bit(2 * eta * k + eta + j)).sum::<i16>();
+            *r = a - b;
+        }
+        r
+    }
+
+    fn check<const N: usize>() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let mut v = [0u8; N];
+            rng.fill(&mut v[..]);
+            let block = PolyBlock::cbd(v);
+            let expected = reference(v);
+            for j in 0..8 {
+                assert_eq!(block[j].0, expected[j]);
+            }
+        }
+    }
+
+    #[test]
+    fn cbd() {
+        check::<2>();
+        check::<4>();
+        check::<6>();
+        check::<8>();
+    }
+    // the `j`-th value is the bits `3 j..3 j + 3` of the little endian 24 bit integer
+    #[test]
+    fn compress_3() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let mut b = [0u8; 3];
+            rng.fill(&mut b[..]);
+            let block = PolyBlock::decompress::<3>(&b);
+            let x = u32::from(b[0]) | u32::from(b[1]) << 8 | u32::from(b[2]) << 16;
+            for j in 0..8 {
+                assert_eq!(u32::from(block[j].compress::<3>()), (x >> (3 * j)) & 7);
+            }
+            assert_eq!(block.compress_3(), b);
+        }
+    }
+}
@@ -1,10 +1,29 @@
-use core::ops::{Add, Sub, Mul};
+use core::ops::{Add, Sub, Mul, Neg, AddAssign, SubAssign, MulAssign};
 
+use subtle::{Choice, ConditionallySelectable};
 use zeroize::Zeroize;
 
+/// An integer modulo `q`, the `i16` is one of its representatives. The functions expect
+/// different ranges of the representative:
+///
+/// - canonical, `0..q`, the 12 bit encoding, `from_canonical` and `unpack` give it,
+///   `pack` and `compress` return it;
+/// - signed, `-q..q`, what `pack` and `compress` take, the result of `montgomery_reduce`;
+/// - centered, `-(q - 1) / 2..=(q - 1) / 2`, the result of `barrett_reduce`;
+/// - the Montgomery domain, the product `mul` gives `a * b * 2^-16`, `MONT` is `2^16`.
+///
+/// The field is public for the internal loops, which keep the bounds themselves.
 #[derive(Clone, Copy, PartialEq, Eq, Zeroize)]
+#[repr(transparent)]
 pub struct Coefficient(pub i16);
 
+impl ConditionallySelectable for Coefficient {
+    #[inline]
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Coefficient(i16::conditional_select(&a.0, &b.0, choice))
+    }
+}
+
 impl Coefficient {
     pub const Q: i16 = 3329;
 
@@ -15,8 +34,9 @@ impl Coefficient {
 
     pub const F: Self = Coefficient(1441);
 
+    /// `a * 2^-16 mod q`, the result is in `-(q - 1)..q` if `a` is in `-q * 2^15..q * 2^15`.
     #[inline]
-    const fn montgomery_reduce(a: i32) -> Self {
+    pub const fn montgomery_reduce(a: i32) -> Self {
         let ua = a.wrapping_mul(Self::Q_INV) as i16;
         let mut t = (ua as i32) * (Self::Q as i32);
         t = a - t;
@@ -24,13 +44,15 @@ impl Coefficient {
         Coefficient(t as i16)
     }
 
+    /// `a mod q`, the centered representative, in `-(q - 1) / 2..=(q - 1) / 2` for any `a`.
     #[inline]
     pub const fn barrett_reduce(a: i16) -> Self {
         let v = ((1u32 << 26) / (Self::Q as u32) + 1) as i32;
         let mut t = v * (a as i32) + (1 << 25);
         t >>= 26;
         t *= Self::Q as i32;
-        Coefficient(a - t as i16)
+        // `t` is out of `i16` near the ends of the range, the result is not
+        Coefficient(a.wrapping_sub(t as i16))
     }
 
     #[inline]
@@ -40,31 +62,70 @@ impl Coefficient {
         u as u16
     }
 
+    /// Casts without a check, `a` must be canonical, see `from_canonical`.
     #[inline]
     pub const fn unpack(a: u16) -> Self {
         Coefficient(a as i16)
     }
 
+    /// The coefficient if `x` is canonical, less than `q`, `None` otherwise.
+    #[inline]
+    pub const fn from_canonical(x: u16) -> Option<Self> {
+        if x < Self::Q as u16 {
+            Some(Coefficient(x as i16))
+        } else {
+            None
+        }
+    }
+
+    /// `round(2^X * x / q) mod 2^X` of the canonical representative `x`,
+    /// computed as `((x << X) + q / 2) / q`, so the halves round up.
     #[inline]
     pub const fn compress<const X: u32>(self) -> u16 {
         let mask = (1 << X) - 1;
         (((((self.pack() as u32) << X) + Self::Q as u32 / 2) / Self::Q as u32) & mask) as u16
     }
 
+    /// `round(q * b / 2^X)`, computed as `(b * q + 2^(X - 1)) >> X`, so the halves round up.
+    /// The bits of `b` above `X` are ignored.
     #[inline]
-    pub fn decompress<const X: u32>(b: u16) -> Self {
+    pub const fn decompress<const X: u32>(b: u16) -> Self {
         let mask = (1 << X) - 1;
         let add = 1 << (X - 1);
-        Coefficient((((i32::from(b) & mask) * i32::from(Self::Q) + add) >> X) as i16)
+        Coefficient(((((b as i32) & mask) * (Self::Q as i32) + add) >> X) as i16)
+    }
+
+    /// The largest distance modulo `q` between a coefficient and the result
+    /// of `decompress::<X>(compress::<X>(c))`, it is at most `ceil(q / 2^(X + 1))`.
+    #[must_use]
+    pub const fn compress_round_trip_error<const X: u32>() -> i16 {
+        let mut max = 0;
+        let mut x = 0;
+        while x < Self::Q {
+            let y = Self::decompress::<X>(Coefficient(x).compress::<X>()).0;
+            let mut d = (x - y).abs();
+            if d > Self::Q - d {
+                d = Self::Q - d;
+            }
+            if d > max {
+                max = d;
+            }
+            x += 1;
+        }
+        max
     }
 
+    /// `compress::<1>` without the division, it runs on the decrypted message and the division
+    /// is not constant time on some targets. Multiplies by `80635 / 2^28`, slightly less
+    /// than `1 / q`, so the rounding constant is `(q + 1) / 2` instead of `q / 2`,
+    /// the result is the same, as in the reference implementation.
     #[inline]
     pub const fn compress_1(self) -> u8 {
-        let mut t = self.pack() as i16;
-        t = (((t << 1) + Coefficient::Q / 2) / Coefficient::Q) & 1;
-        t as u8
+        let t = ((self.pack() as u32) << 1) + (Coefficient::Q as u32 + 1) / 2;
+        ((t * 80_635) >> 28) as u8 & 1
     }
 
+    /// `decompress::<1>`, selects `(q + 1) / 2` by the mask, without a branch.
     #[inline]
     pub const fn decompress_1(b: u8) -> Self {
         let mask = ((b as u16) & 1).wrapping_neg();
@@ -77,7 +138,6 @@ impl Coefficient {
     }
 
     #[inline]
-    #[allow(dead_code)]
     pub const fn zeta(i: usize, bits: u32) -> Self {
         #[inline]
         const fn reverse_bits(mut i: usize, mut bits: u32) -> usize {
@@ -92,13 +152,14 @@ impl Coefficient {
         }
 
         #[inline]
-        const fn tmp(i: usize) -> Coefficient {
-            if i == 0 {
-                Coefficient::MONT
-            } else {
-                let m = Coefficient((Coefficient::MONT.0 * 17) % Coefficient::Q);
-                tmp(i - 1).mul(m)
+        const fn tmp(mut i: usize) -> Coefficient {
+            let m = Coefficient((Coefficient::MONT.0 * 17) % Coefficient::Q);
+            let mut r = Coefficient::MONT;
+            while i > 0 {
+                r = r.mul(m);
+                i -= 1;
             }
+            r
         }
 
         let z = tmp(reverse_bits(i, bits));
@@ -126,7 +187,7 @@ impl Sub for Coefficient {
 
     #[inline]
     fn sub(self, rhs: Self) -> Self::Output {
-        Coefficient(self.0 - rhs.0)
+        Coefficient::barrett_reduce(self.0.wrapping_sub(rhs.0))
     }
 }
 
@@ -139,8 +200,40 @@ impl Mul for Coefficient {
     }
 }
 
+impl Neg for Coefficient {
+    type Output = Coefficient;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Coefficient::barrett_reduce(self.0.wrapping_neg())
+    }
+}
+
+impl AddAssign for Coefficient {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Coefficient {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for Coefficient {
+    #[inline]
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use rand::Rng;
+
     use super::Coefficient;
 
     #[test]
@@ -160,4 +253,135 @@ mod tests {
             assert_eq!(Coefficient::zeta(i, 7).0, x);
         }
     }
+
+    #[test]
+    fn assign_ops() {
+        let a = Coefficient(1234);
+        let b = Coefficient(-2345);
+
+        let mut c = a;
+        c += b;
+        assert_eq!(c.pack(), (a + b).pack());
+        c -= b;
+        assert_eq!(c.pack(), a.pack());
+        c *= b;
+        assert_eq!(c.pack(), (a * b).pack());
+
+        assert_eq!((-a).pack(), (Coefficient::Q - 1234) as u16);
+        assert_eq!((a + -a).pack(), 0);
+    }
+
+    fn check_compress<const X: u32>() {
+        let bound = Coefficient::compress_round_trip_error::<X>();
+        let q = i32::from(Coefficient::Q);
+        assert!(i32::from(bound) <= (q + (1 << (X + 1)) - 1) >> (X + 1));
+
+        // every representative, the negative ones are compressed as the canonical ones
+        for x in -(Coefficient::Q - 1)..Coefficient::Q {
+            let c = Coefficient(x);
+            let b = c.compress::<X>();
+            assert!(b < 1 << X);
+            let expected = ((i32::from(c.pack()) << X) + q / 2) / q;
+            assert_eq!(i32::from(b), expected % (1 << X));
+
+            let d = (i32::from(c.pack()) - i32::from(Coefficient::decompress::<X>(b).0)).abs();
+            assert!(d.min(q - d) <= i32::from(bound));
+        }
+    }
+
+    #[test]
+    fn compress() {
+        check_compress::<1>();
+        check_compress::<3>();
+        check_compress::<4>();
+        check_compress::<5>();
+        check_compress::<10>();
+        check_compress::<11>();
+
+        assert_eq!(Coefficient::compress_round_trip_error::<1>(), 832);
+        assert_eq!(Coefficient::compress_round_trip_error::<10>(), 2);
+    }
+
+    #[test]
+    fn barrett_reduce() {
+        let q = i32::from(Coefficient::Q);
+        for a in i16::MIN..=i16::MAX {
+            let r = i32::from(Coefficient::barrett_reduce(a).0);
+            assert!(r.abs() <= (q - 1) / 2, "{a} -> {r}");
+            assert_eq!((i32::from(a) - r).rem_euclid(q), 0, "{a} -> {r}");
+        }
+    }
+
+    fn check_montgomery_reduce(a: i32) {
+        let q = i32::from(Coefficient::Q);
+        let r = i32::from(Coefficient::montgomery_reduce(a).0);
+        assert!(r.abs() < q, "{a} -> {r}");
+        // `r * 2^16 = a mod q`
+        assert_eq!((r * (1 << 16) - a).rem_euclid(q), 0, "{a} -> {r}");
+    }
+
+    #[test]
+    fn montgomery_reduce() {
+        let q = i32::from(Coefficient::Q);
+        let bound = q << 15;
+
+        // every product of two canonical coefficients, what `mul` reduces
+        for x in 0..q {
+            for y in 0..q {
+                check_montgomery_reduce(x * y);
+            }
+        }
+        // the ends of the range and random values in it
+        for a in (-bound..(-bound + 0x10000)).chain((bound - 0x10000)..bound) {
+            check_montgomery_reduce(a);
+        }
+        let mut rng = rand::thread_rng();
+        for _ in 0..(1 << 20) {
+            check_montgomery_reduce(rng.gen_range(-bound..bound));
+        }
+    }
+
+    #[test]
+    fn from_canonical() {
+        for x in 0..=u16::MAX {
+            match Coefficient::from_canonical(x) {
+                Some(c) => assert_eq!(c.pack(), x),
+                None => assert!(x >= Coefficient::Q as u16),
+            }
+        }
+        assert!(Coefficient::from_canonical(Coefficient::Q as u16 - 1).is_some());
+        assert!(Coefficient::from_canonical(Coefficient::Q as u16).is_none());
+    }
+
+    // the same as the division, `(2 t + q / 2) / q mod 2`, for every representative
+    #[test]
+    fn compress_1() {
+        let q = i32::from(Coefficient::Q);
+        for x in -(Coefficient::Q - 1)..Coefficient::Q {
+            let c = Coefficient(x);
+            let expected = (((i32::from(c.pack()) << 1) + q / 2) / q) & 1;
+            assert_eq!(i32::from(c.compress_1()), expected);
+            assert_eq!(c.compress_1(), c.compress::<1>() as u8);
+        }
+        for b in 0..=u8::MAX {
+            assert!(Coefficient::decompress_1(b) == Coefficient::decompress::<1>(b.into()));
+        }
+    }
+
+    #[test]
+    fn sub_chain() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..64 {
+            let mut c = Coefficient(0);
+            let mut expected = 0i64;
+            for _ in 0..10_000 {
+                let x = rng.gen_range(-(Coefficient::Q - 1)..Coefficient::Q);
+                c -= Coefficient(x);
+                expected -= i64::from(x);
+                assert!(c.0.abs() < Coefficient::Q);
+            }
+            let expected = expected.rem_euclid(i64::from(Coefficient::Q));
+            assert_eq!(i64::from(c.pack()), expected);
+        }
+    }
 }
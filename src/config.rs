@@ -1,43 +1,55 @@
-use sha3::{Shake256, digest::Update};
+use sha3::digest::Update;
 use subtle::{Choice, ConstantTimeEq};
 
-use super::{coefficient::Coefficient, poly::Poly};
+use super::{
+    coefficient::Coefficient,
+    poly::{Poly, CompressIter},
+    symmetric::Symmetric,
+};
 
+/// The parameter set. The implementation sets the constants, the methods follow from them,
+/// so a new parameter set is a new set of the constants.
 pub trait Config<const SIZE: usize> {
-    const COMPRESSED_SIZE: usize;
+    /// The bits per coefficient of the compressed vector of the cipher text, `du`.
+    const DU: u32;
 
-    fn get_noise(seed: &[u8; 32], nonce: usize) -> Poly<SIZE, true>;
+    /// The bits per coefficient of the compressed polynomial of the cipher text, `dv`.
+    const DV: u32;
 
-    fn decompress_vec(bytes: &[u8]) -> Poly<SIZE, true>;
+    /// The parameter of the noise in the secret and in the public key.
+    const ETA1: usize;
 
-    fn compress_vec<U>(poly: &Poly<SIZE, true>, update: &mut U)
-    where
-        U: Update;
+    /// The parameter of the noise in the cipher text.
+    const ETA2: usize;
 
-    fn compare_vec(lhs: &Coefficient, rhs: &Coefficient) -> Choice;
+    const COMPRESSED_SIZE: usize = Self::DU as usize * SIZE;
 
-    fn decompress(bytes: &[u8]) -> Poly<SIZE, true>;
+    const COMPRESSED_POLY_SIZE: usize = Self::DV as usize * SIZE;
 
-    fn compress<U>(poly: &Poly<SIZE, true>, update: &mut U)
+    /// The noise of the secret and of the public key, `nonce` is the one byte
+    /// domain separator of `PRF`.
+    #[inline]
+    #[must_use]
+    fn get_noise<S>(seed: &[u8; 32], nonce: u8) -> Poly<SIZE, true>
     where
-        U: Update;
-
-    fn compare(lhs: &Coefficient, rhs: &Coefficient) -> Choice;
-}
-
-pub struct Dim<const DIM: usize>;
-
-impl<const SIZE: usize> Config<SIZE> for Dim<2> {
-    const COMPRESSED_SIZE: usize = 10 * SIZE;
+        S: Symmetric,
+    {
+        get_noise::<S, SIZE>(Self::ETA1, seed, nonce)
+    }
 
     #[inline]
-    fn get_noise(seed: &[u8; 32], nonce: usize) -> Poly<SIZE, true> {
-        Poly::get_noise::<Shake256, 6>(seed, nonce)
+    #[must_use]
+    fn get_noise_e2<S>(seed: &[u8; 32], nonce: u8) -> Poly<SIZE, true>
+    where
+        S: Symmetric,
+    {
+        get_noise::<S, SIZE>(Self::ETA2, seed, nonce)
     }
 
     #[inline]
+    #[must_use]
     fn decompress_vec(bytes: &[u8]) -> Poly<SIZE, true> {
-        Poly::decompress::<10>(bytes)
+        decompress(Self::DU, bytes)
     }
 
     #[inline]
@@ -45,19 +57,25 @@ impl<const SIZE: usize> Config<SIZE> for Dim<2> {
     where
         U: Update,
     {
-        poly.compress::<U, 10>(update);
+        compress(Self::DU, poly, update);
     }
 
+    /// The bytes `compress_vec` gives, computed lazily.
     #[inline]
+    fn compress_vec_iter(poly: &Poly<SIZE, true>) -> CompressIter<'_> {
+        compress_iter(Self::DU, poly)
+    }
+
+    #[inline]
+    #[must_use]
     fn compare_vec(lhs: &Coefficient, rhs: &Coefficient) -> Choice {
-        let ai = lhs.compress::<10>();
-        let bi = rhs.compress::<10>();
-        ai.ct_eq(&bi)
+        compare(Self::DU, *lhs, *rhs)
     }
 
     #[inline]
+    #[must_use]
     fn decompress(bytes: &[u8]) -> Poly<SIZE, true> {
-        Poly::decompress::<4>(bytes)
+        decompress(Self::DV, bytes)
     }
 
     #[inline]
@@ -65,111 +83,156 @@ impl<const SIZE: usize> Config<SIZE> for Dim<2> {
     where
         U: Update,
     {
-        poly.compress::<U, 4>(update);
+        compress(Self::DV, poly, update);
+    }
+
+    /// The bytes `compress` gives, computed lazily.
+    #[inline]
+    fn compress_iter(poly: &Poly<SIZE, true>) -> CompressIter<'_> {
+        compress_iter(Self::DV, poly)
     }
 
     #[inline]
+    #[must_use]
     fn compare(lhs: &Coefficient, rhs: &Coefficient) -> Choice {
-        let ai = lhs.compress::<4>();
-        let bi = rhs.compress::<4>();
-        ai.ct_eq(&bi)
+        compare(Self::DV, *lhs, *rhs)
     }
 }
 
-impl<const SIZE: usize> Config<SIZE> for Dim<3> {
-    const COMPRESSED_SIZE: usize = 10 * SIZE;
+pub struct Dim<const DIM: usize>;
 
-    #[inline]
-    fn get_noise(seed: &[u8; 32], nonce: usize) -> Poly<SIZE, true> {
-        Poly::get_noise::<Shake256, 4>(seed, nonce)
+/// The parameter sets, `(du, dv, eta1, eta2)` for each of Kyber512, Kyber768 and Kyber1024.
+/// `Config` is implemented for these dimensions only, so any other one does not satisfy the bound.
+const fn params(dim: usize) -> (u32, u32, usize, usize) {
+    match dim {
+        2 => (10, 4, 3, 2),
+        3 => (10, 4, 2, 2),
+        4 => (11, 5, 2, 2),
+        _ => panic!("unsupported dimension"),
     }
+}
 
-    #[inline]
-    fn decompress_vec(bytes: &[u8]) -> Poly<SIZE, true> {
-        Poly::decompress::<10>(bytes)
+/// The values the helpers below dispatch on, checked for each row of the tables.
+const fn supported((du, dv, eta1, eta2): (u32, u32, usize, usize)) -> bool {
+    const fn bits(d: u32) -> bool {
+        matches!(d, 3 | 4 | 5 | 10 | 11)
     }
 
-    #[inline]
-    fn compress_vec<U>(poly: &Poly<SIZE, true>, update: &mut U)
-    where
-        U: Update,
-    {
-        poly.compress::<U, 10>(update);
+    const fn eta(eta: usize) -> bool {
+        matches!(eta, 2 | 3)
     }
 
-    #[inline]
-    fn compare_vec(lhs: &Coefficient, rhs: &Coefficient) -> Choice {
-        let ai = lhs.compress::<10>();
-        let bi = rhs.compress::<10>();
-        ai.ct_eq(&bi)
-    }
+    bits(du) && bits(dv) && eta(eta1) && eta(eta2)
+}
 
-    #[inline]
-    fn decompress(bytes: &[u8]) -> Poly<SIZE, true> {
-        Poly::decompress::<4>(bytes)
-    }
+const _: () = assert!(supported(params(2)) && supported(params(3)) && supported(params(4)));
 
-    #[inline]
-    fn compress<U>(poly: &Poly<SIZE, true>, update: &mut U)
-    where
-        U: Update,
-    {
-        poly.compress::<U, 4>(update);
-    }
+// The implementations for each of the given dimensions, the constants are taken from the table.
+macro_rules! impl_config {
+    ($ty:ident, $params:ident, $($dim:literal),+) => {
+        $(
+            impl Config<32> for $ty<$dim> {
+                const DU: u32 = $params($dim).0;
 
-    #[inline]
-    fn compare(lhs: &Coefficient, rhs: &Coefficient) -> Choice {
-        let ai = lhs.compress::<4>();
-        let bi = rhs.compress::<4>();
-        ai.ct_eq(&bi)
-    }
+                const DV: u32 = $params($dim).1;
+
+                const ETA1: usize = $params($dim).2;
+
+                const ETA2: usize = $params($dim).3;
+            }
+        )+
+    };
 }
 
-impl<const SIZE: usize> Config<SIZE> for Dim<4> {
-    const COMPRESSED_SIZE: usize = 11 * SIZE;
+impl_config!(Dim, params, 2, 3, 4);
 
-    #[inline]
-    fn get_noise(seed: &[u8; 32], nonce: usize) -> Poly<SIZE, true> {
-        Poly::get_noise::<Shake256, 4>(seed, nonce)
-    }
+/// The round 2 parameter sets, LEGACY, for the interop with old peers only, see `kem::round2`.
+///
+/// Kyber768 and Kyber1024 are the same as `Dim`. Kyber512 compresses `v` to 3 bits and samples
+/// the secret with `eta1 = 2`, round 3 changed both to lower the decryption failure probability
+/// and to raise the security margin.
+#[cfg(feature = "round2")]
+pub struct DimR2<const DIM: usize>;
 
-    #[inline]
-    fn decompress_vec(bytes: &[u8]) -> Poly<SIZE, true> {
-        Poly::decompress::<11>(bytes)
+#[cfg(feature = "round2")]
+const fn params_round2(dim: usize) -> (u32, u32, usize, usize) {
+    match dim {
+        2 => (10, 3, 2, 2),
+        _ => params(dim),
     }
+}
 
-    #[inline]
-    fn compress_vec<U>(poly: &Poly<SIZE, true>, update: &mut U)
-    where
-        U: Update,
-    {
-        poly.compress::<U, 11>(update);
-    }
+#[cfg(feature = "round2")]
+const _: () = assert!(supported(params_round2(2)));
 
-    #[inline]
-    fn compare_vec(lhs: &Coefficient, rhs: &Coefficient) -> Choice {
-        let ai = lhs.compress::<11>();
-        let bi = rhs.compress::<11>();
-        ai.ct_eq(&bi)
+#[cfg(feature = "round2")]
+impl_config!(DimR2, params_round2, 2, 3, 4);
+
+// The parameters are constants of the implementation, so the `match` of each is resolved
+// at compile time, the same code as calling with the literal. The const generic arguments
+// cannot be taken from the associated constants directly.
+
+#[inline]
+fn get_noise<S, const SIZE: usize>(eta: usize, seed: &[u8; 32], nonce: u8) -> Poly<SIZE, true>
+where
+    S: Symmetric,
+{
+    // `2 * eta` bits per coefficient, so `2 * eta` bytes per block of 8 coefficients
+    match eta {
+        2 => Poly::get_noise::<S, 4>(seed, nonce),
+        3 => Poly::get_noise::<S, 6>(seed, nonce),
+        _ => unreachable!("unsupported eta {eta}"),
     }
+}
 
-    #[inline]
-    fn decompress(bytes: &[u8]) -> Poly<SIZE, true> {
-        Poly::decompress::<5>(bytes)
+#[inline]
+fn decompress<const SIZE: usize>(x: u32, bytes: &[u8]) -> Poly<SIZE, true> {
+    match x {
+        3 => Poly::decompress::<3>(bytes),
+        4 => Poly::decompress::<4>(bytes),
+        5 => Poly::decompress::<5>(bytes),
+        10 => Poly::decompress::<10>(bytes),
+        11 => Poly::decompress::<11>(bytes),
+        _ => unreachable!("unsupported du or dv {x}"),
     }
+}
 
-    #[inline]
-    fn compress<U>(poly: &Poly<SIZE, true>, update: &mut U)
-    where
-        U: Update,
-    {
-        poly.compress::<U, 5>(update);
+#[inline]
+fn compress<U, const SIZE: usize>(x: u32, poly: &Poly<SIZE, true>, update: &mut U)
+where
+    U: Update,
+{
+    match x {
+        3 => poly.compress::<U, 3>(update),
+        4 => poly.compress::<U, 4>(update),
+        5 => poly.compress::<U, 5>(update),
+        10 => poly.compress::<U, 10>(update),
+        11 => poly.compress::<U, 11>(update),
+        _ => unreachable!("unsupported du or dv {x}"),
     }
+}
 
-    #[inline]
-    fn compare(lhs: &Coefficient, rhs: &Coefficient) -> Choice {
-        let ai = lhs.compress::<5>();
-        let bi = rhs.compress::<5>();
-        ai.ct_eq(&bi)
+#[inline]
+fn compress_iter<const SIZE: usize>(x: u32, poly: &Poly<SIZE, true>) -> CompressIter<'_> {
+    match x {
+        3 => poly.compress_iter::<3>(),
+        4 => poly.compress_iter::<4>(),
+        5 => poly.compress_iter::<5>(),
+        10 => poly.compress_iter::<10>(),
+        11 => poly.compress_iter::<11>(),
+        _ => unreachable!("unsupported du or dv {x}"),
     }
 }
+
+#[inline]
+fn compare(x: u32, lhs: Coefficient, rhs: Coefficient) -> Choice {
+    let (ai, bi) = match x {
+        3 => (lhs.compress::<3>(), rhs.compress::<3>()),
+        4 => (lhs.compress::<4>(), rhs.compress::<4>()),
+        5 => (lhs.compress::<5>(), rhs.compress::<5>()),
+        10 => (lhs.compress::<10>(), rhs.compress::<10>()),
+        11 => (lhs.compress::<11>(), rhs.compress::<11>()),
+        _ => unreachable!("unsupported du or dv {x}"),
+    };
+    ai.ct_eq(&bi)
+}
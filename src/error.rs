@@ -0,0 +1,78 @@
+use core::fmt;
+
+/// The error of deserialization of keys and cipher texts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    UnexpectedLength {
+        expected: usize,
+        got: usize,
+    },
+    /// The 12 bit encoded coefficient is not reduced modulo `q`.
+    NonCanonicalCoefficient,
+    /// The hash of the public key stored in the serialized key pair is not the hash of the key.
+    PublicKeyHashMismatch,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedLength { expected, got } => {
+                write!(f, "unexpected length {got}, expected {expected}")
+            }
+            DecodeError::NonCanonicalCoefficient => write!(f, "non canonical coefficient"),
+            DecodeError::PublicKeyHashMismatch => write!(f, "public key hash mismatch"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// The fresh key pair failed the pairwise consistency test,
+/// indicates a hardware fault or a bug, the key pair must not be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsistencyError;
+
+impl fmt::Display for ConsistencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key pair failed the pairwise consistency test")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConsistencyError {}
+
+/// The seed is all zero, e.g. uninitialized memory, the key would be known to everyone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeakSeed;
+
+impl fmt::Display for WeakSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the seed is all zero")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WeakSeed {}
+
+// the crate is `no_std` unless testing or the `std` feature is enabled,
+// so the ordinary build checks the error is usable without `std`
+#[cfg(not(any(test, feature = "std")))]
+const _: fn() = || {
+    const fn check<E: fmt::Debug + fmt::Display + Copy + Eq>() {}
+    check::<DecodeError>();
+    check::<ConsistencyError>();
+    check::<WeakSeed>();
+};
+
+#[inline]
+pub const fn check_length(bytes: &[u8], expected: usize) -> Result<(), DecodeError> {
+    if bytes.len() == expected {
+        Ok(())
+    } else {
+        Err(DecodeError::UnexpectedLength {
+            expected,
+            got: bytes.len(),
+        })
+    }
+}
@@ -1,7 +1,12 @@
-use sha3::digest::{Update, XofReader, ExtendableOutput};
+use sha3::digest::XofReader;
+
+// the rate of SHAKE128, multiple of 3, so a block holds whole pairs of samples
+const BLOCK: usize = 168;
 
 pub struct Buf<R> {
     xof: R,
+    buffer: [u8; BLOCK],
+    position: usize,
     remain: Option<u16>,
 }
 
@@ -9,15 +14,11 @@ impl<R> Buf<R>
 where
     R: XofReader,
 {
-    pub fn new<D>(seed: &[u8; 32], i: usize, j: usize) -> Self
-    where
-        D: Default + Update + ExtendableOutput<Reader = R>,
-    {
+    pub const fn new(xof: R) -> Self {
         Buf {
-            xof: D::default()
-                .chain(seed)
-                .chain(&[i as u8, j as u8])
-                .finalize_xof(),
+            xof,
+            buffer: [0; BLOCK],
+            position: BLOCK,
             remain: None,
         }
     }
@@ -32,8 +33,12 @@ where
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
         let it = self.remain.take().unwrap_or_else(|| {
-            let mut buf = [0; 3];
-            self.xof.read(&mut buf);
+            if self.position == BLOCK {
+                self.xof.read(&mut self.buffer);
+                self.position = 0;
+            }
+            let buf = &self.buffer[self.position..(self.position + 3)];
+            self.position += 3;
             let v = (u16::from(buf[0]) | u16::from(buf[1]) << 8) & 0xFFF;
             self.remain = Some((u16::from(buf[1] >> 4) | u16::from(buf[2]) << 4) & 0xFFF);
             v
@@ -1,14 +1,19 @@
 use sha3::{
-    Sha3_512, Shake256, Shake128,
+    Sha3_512,
     digest::{Update, FixedOutput},
 };
 use subtle::{ConstantTimeEq, Choice};
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
+#[cfg(feature = "noise-analysis")]
+use super::coefficient::Coefficient;
 use super::{
+    error::{DecodeError, check_length},
     array::Array,
-    poly::{Poly, Ntt, PolyMul},
+    block::PolyBlock,
+    poly::{Poly, Ntt, PolyMul, Message},
     config::{Dim, Config},
+    symmetric::{Symmetric, Shake},
 };
 
 #[derive(Clone)]
@@ -16,6 +21,13 @@ pub struct SecretKey<const DIM: usize, const SIZE: usize> {
     poly_vector: Array<Poly<SIZE, false>, DIM>,
 }
 
+// the marker alone does not wipe, the key is also used bare, see `kem::cpa`
+impl<const DIM: usize, const SIZE: usize> Drop for SecretKey<DIM, SIZE> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 impl<const DIM: usize, const SIZE: usize> ZeroizeOnDrop for SecretKey<DIM, SIZE> {}
 
 impl<const DIM: usize, const SIZE: usize> Zeroize for SecretKey<DIM, SIZE> {
@@ -28,9 +40,11 @@ impl<const DIM: usize, const SIZE: usize> Zeroize for SecretKey<DIM, SIZE> {
     }
 }
 
+// with `compact-public-key` feature the matrix is not stored, its rows are sampled on demand
 #[derive(Clone, PartialEq, Eq)]
 pub struct PublicKey<const DIM: usize, const SIZE: usize> {
     poly_vector: Array<Poly<SIZE, false>, DIM>,
+    #[cfg(not(feature = "compact-public-key"))]
     matrix: Array<Array<Poly<SIZE, false>, DIM>, DIM>,
     seed: [u8; 32],
 }
@@ -45,6 +59,7 @@ impl<const DIM: usize, const SIZE: usize> Zeroize for PublicKey<DIM, SIZE> {
             }
         }
 
+        #[cfg(not(feature = "compact-public-key"))]
         for row in self.matrix.as_mut() {
             for v in row.as_mut() {
                 for i in 0..(SIZE * 8) {
@@ -57,11 +72,58 @@ impl<const DIM: usize, const SIZE: usize> Zeroize for PublicKey<DIM, SIZE> {
     }
 }
 
+impl<const DIM: usize, const SIZE: usize> ConstantTimeEq for SecretKey<DIM, SIZE> {
+    #[inline]
+    fn ct_eq(&self, other: &Self) -> Choice {
+        (0..DIM).fold(Choice::from(1), |x, i| {
+            x & self.poly_vector[i].ct_eq(&other.poly_vector[i])
+        })
+    }
+}
+
+impl<const DIM: usize, const SIZE: usize> ConstantTimeEq for PublicKey<DIM, SIZE> {
+    #[inline]
+    fn ct_eq(&self, other: &Self) -> Choice {
+        (0..DIM).fold(self.seed.ct_eq(&other.seed), |x, i| {
+            x & self.poly_vector[i].ct_eq(&other.poly_vector[i])
+        })
+    }
+}
+
+#[derive(Clone)]
 pub struct CipherText<const DIM: usize, const SIZE: usize> {
     poly_vector: Array<Poly<SIZE, true>, DIM>,
     poly: Poly<SIZE, true>,
 }
 
+// wipes itself on drop, the re-encrypted cipher text in decapsulation depends on the message
+impl<const DIM: usize, const SIZE: usize> Drop for CipherText<DIM, SIZE> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<const DIM: usize, const SIZE: usize> ZeroizeOnDrop for CipherText<DIM, SIZE> {}
+
+impl<const DIM: usize, const SIZE: usize> Zeroize for CipherText<DIM, SIZE> {
+    fn zeroize(&mut self) {
+        for v in self.poly_vector.as_mut() {
+            for i in 0..(SIZE * 8) {
+                v[i].zeroize();
+            }
+        }
+        for i in 0..(SIZE * 8) {
+            self.poly[i].zeroize();
+        }
+    }
+}
+
+/// The cipher text with the vector `b` transformed to NTT domain.
+pub struct PreparedCipherText<const DIM: usize, const SIZE: usize> {
+    poly_vector: Array<Poly<SIZE, false>, DIM>,
+    poly: Poly<SIZE, true>,
+}
+
 pub fn split(mut x: [u8; 64]) -> ([u8; 32], [u8; 32]) {
     let mut a = [0; 32];
     a.clone_from_slice(&x[..32]);
@@ -71,77 +133,88 @@ pub fn split(mut x: [u8; 64]) -> ([u8; 32], [u8; 32]) {
     (a, b)
 }
 
-pub fn key_pair<const DIM: usize, const SIZE: usize>(
+/// The same as `split`, the halves wipe themselves on drop, so the caller cannot forget to.
+pub fn split_zeroizing(x: [u8; 64]) -> (Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>) {
+    let (a, b) = split(x);
+    (Zeroizing::new(a), Zeroizing::new(b))
+}
+
+pub fn key_pair<const DIM: usize, const SIZE: usize, C, S>(
     seed: &[u8; 32],
 ) -> (SecretKey<DIM, SIZE>, PublicKey<DIM, SIZE>)
 where
-    Dim<DIM>: Config<SIZE>,
+    C: Config<SIZE>,
     Poly<SIZE, false>: PolyMul,
     Poly<SIZE, true>: Ntt<Output = Poly<SIZE, false>>,
+    S: Symmetric,
 {
-    let c = Sha3_512::default().chain(seed).finalize_fixed().into();
-    let (seed, mut noise_seed) = split(c);
-
-    let sk_pv: Array<_, DIM> = (0..DIM)
-        .map(|i| <Dim<DIM> as Config<SIZE>>::get_noise(&noise_seed, i).ntt())
-        .collect();
+    let c = S::G::default().chain(seed).finalize_fixed().into();
+    key_pair_expanded::<DIM, SIZE, C, S>(c)
+}
 
-    let a: Array<Array<Poly<SIZE, false>, DIM>, DIM> = (0..DIM)
-        .map(|i| {
-            (0..DIM)
-                .map(|j| Poly::get_uniform::<Shake128>(&seed, i, j))
-                .collect()
-        })
-        .collect();
+/// FIPS 203 key generation, the dimension is appended to the seed for domain separation.
+pub fn key_pair_separated<const DIM: usize, const SIZE: usize>(
+    seed: &[u8; 32],
+) -> (SecretKey<DIM, SIZE>, PublicKey<DIM, SIZE>)
+where
+    Dim<DIM>: Config<SIZE>,
+    Poly<SIZE, false>: PolyMul,
+    Poly<SIZE, true>: Ntt<Output = Poly<SIZE, false>>,
+{
+    let c = Sha3_512::default()
+        .chain(seed)
+        .chain([DIM as u8])
+        .finalize_fixed()
+        .into();
+    key_pair_expanded::<DIM, SIZE, Dim<DIM>, Shake>(c)
+}
 
-    let pk_pv: Array<Poly<SIZE, false>, DIM> = (0..DIM)
-        .map(|i| {
-            let row = (0..DIM).map(|j| &a[j][i]);
-            let mut p = Poly::mul_fold_montgomery(row, sk_pv.as_ref().iter()).montgomery_reduce();
-            let e = <Dim<DIM> as Config<SIZE>>::get_noise(&noise_seed, DIM + i).ntt();
-            p += &e;
-            p.barrett_reduce()
-        })
-        .collect();
+fn key_pair_expanded<const DIM: usize, const SIZE: usize, C, S>(
+    c: [u8; 64],
+) -> (SecretKey<DIM, SIZE>, PublicKey<DIM, SIZE>)
+where
+    C: Config<SIZE>,
+    Poly<SIZE, false>: PolyMul,
+    Poly<SIZE, true>: Ntt<Output = Poly<SIZE, false>>,
+    S: Symmetric,
+{
+    let (seed, noise_seed) = split_zeroizing(c);
 
-    noise_seed.zeroize();
+    let sk_pv = rows(|i| C::get_noise::<S>(&noise_seed, nonce(i)).ntt());
 
     let sk = SecretKey { poly_vector: sk_pv };
-    let pk = PublicKey {
-        poly_vector: pk_pv,
-        matrix: a,
-        seed,
-    };
+    let pk = sk.derive_public::<C, S>(&seed, &noise_seed);
 
     (sk, pk)
 }
 
-pub fn encapsulate<const DIM: usize, const SIZE: usize>(
+pub fn encapsulate<const DIM: usize, const SIZE: usize, C, S>(
     noise_seed: &[u8; 32],
-    message: &[u8; SIZE],
+    message: &Message,
     public_key: &PublicKey<DIM, SIZE>,
 ) -> CipherText<DIM, SIZE>
 where
-    Dim<DIM>: Config<SIZE>,
+    C: Config<SIZE>,
     Poly<SIZE, false>: PolyMul + Ntt<Output = Poly<SIZE, true>>,
     Poly<SIZE, true>: Ntt<Output = Poly<SIZE, false>>,
+    S: Symmetric,
 {
     let sp: Array<_, DIM> = (0..DIM)
-        .map(|i| <Dim<DIM> as Config<SIZE>>::get_noise(noise_seed, i).ntt())
+        .map(|i| C::get_noise::<S>(noise_seed, nonce(i)).ntt())
         .collect();
 
-    let a = &public_key.matrix;
     let pk_pv = &public_key.poly_vector;
 
     let b = (0..DIM)
         .map(|i| {
-            let mut b = Poly::mul_fold_montgomery(a[i].as_ref().iter(), sp.as_ref().iter()).ntt();
-            b += &Poly::get_noise::<Shake256, 4>(noise_seed, i + DIM);
+            let row = public_key.matrix_row::<S>(i);
+            let mut b = Poly::mul_fold_montgomery(row.iter(), sp.iter()).ntt();
+            b += &C::get_noise_e2::<S>(noise_seed, nonce(i + DIM));
             b.barrett_reduce()
         })
         .collect();
-    let mut v = Poly::mul_fold_montgomery(pk_pv.as_ref().iter(), sp.as_ref().iter()).ntt();
-    v += &Poly::get_noise::<Shake256, 4>(noise_seed, 2 * DIM);
+    let mut v = Poly::mul_fold_montgomery(pk_pv.iter(), sp.iter()).ntt();
+    v += &C::get_noise_e2::<S>(noise_seed, nonce(2 * DIM));
     v += &Poly::from_msg(message);
 
     CipherText {
@@ -153,87 +226,437 @@ where
 pub fn decapsulate<const DIM: usize, const SIZE: usize>(
     cipher_text: &CipherText<DIM, SIZE>,
     secret_key: &SecretKey<DIM, SIZE>,
-) -> [u8; SIZE]
+) -> Message
+where
+    Poly<SIZE, false>: PolyMul + Ntt<Output = Poly<SIZE, true>>,
+    Poly<SIZE, true>: Ntt<Output = Poly<SIZE, false>>,
+{
+    decapsulate_prepared(&cipher_text.prepared(), secret_key)
+}
+
+pub fn decapsulate_prepared<const DIM: usize, const SIZE: usize>(
+    cipher_text: &PreparedCipherText<DIM, SIZE>,
+    secret_key: &SecretKey<DIM, SIZE>,
+) -> Message
+where
+    Poly<SIZE, false>: PolyMul + Ntt<Output = Poly<SIZE, true>>,
+{
+    message_poly(cipher_text, secret_key).to_msg()
+}
+
+/// Returns the message and the largest distance of a coefficient of the noisy message
+/// from the nearest multiple of `q / 2`, decryption fails if it is more than `q / 4`.
+/// Not constant time, for the analysis of decryption failures only.
+#[cfg(feature = "noise-analysis")]
+pub fn decapsulate_noisy<const DIM: usize, const SIZE: usize>(
+    cipher_text: &CipherText<DIM, SIZE>,
+    secret_key: &SecretKey<DIM, SIZE>,
+) -> (Message, u16)
+where
+    Poly<SIZE, false>: PolyMul + Ntt<Output = Poly<SIZE, true>>,
+    Poly<SIZE, true>: Ntt<Output = Poly<SIZE, false>>,
+{
+    let mp = message_poly(&cipher_text.prepared(), secret_key);
+    let q = Coefficient::Q as u16;
+    let noise = (0..(SIZE * 8))
+        .map(|i| {
+            let x = mp[i].pack();
+            x.min(q - x).min(x.abs_diff((q + 1) / 2))
+        })
+        .max()
+        .unwrap_or_default();
+    (mp.to_msg(), noise)
+}
+
+/// The message and the margin, see `Poly::to_msg_with_margin`.
+#[cfg(feature = "testing")]
+pub fn decapsulate_margin<const DIM: usize, const SIZE: usize>(
+    cipher_text: &CipherText<DIM, SIZE>,
+    secret_key: &SecretKey<DIM, SIZE>,
+) -> (Message, u16)
 where
     Poly<SIZE, false>: PolyMul + Ntt<Output = Poly<SIZE, true>>,
     Poly<SIZE, true>: Ntt<Output = Poly<SIZE, false>>,
+{
+    message_poly(&cipher_text.prepared(), secret_key).to_msg_with_margin()
+}
+
+// the secret vector is in the NTT domain and reduced since the key generation, nothing to
+// prepare, caching `a1 * zeta` of each pair would save a fifth of the inner product only
+fn message_poly<const DIM: usize, const SIZE: usize>(
+    cipher_text: &PreparedCipherText<DIM, SIZE>,
+    secret_key: &SecretKey<DIM, SIZE>,
+) -> Poly<SIZE, true>
+where
+    Poly<SIZE, false>: PolyMul + Ntt<Output = Poly<SIZE, true>>,
 {
     let b = &cipher_text.poly_vector;
     let v = &cipher_text.poly;
     let sk_pv = &secret_key.poly_vector;
 
-    let mut mp =
-        Poly::mul_fold_montgomery(sk_pv.as_ref().iter(), b.as_ref().iter().map(|b| b.ntt())).ntt();
+    let mut mp = Poly::mul_fold_montgomery(sk_pv.iter(), b.iter()).ntt();
     mp -= v;
-    mp.barrett_reduce().to_msg()
+    mp.barrett_reduce()
+}
+
+/// The matrix `A^T` in the NTT domain, `DIM * DIM` polynomials, the largest part of
+/// the key pair. The key generation builds it and drops it, unless the public key keeps it.
+/// Without the `compact-public-key` feature the public key keeps it for its whole life,
+/// so each encapsulation and decapsulation reuses it. With the feature the public key
+/// keeps the seed only, the encapsulation samples one row at a time, see `matrix_row`.
+pub fn matrix_from_seed<const DIM: usize, const SIZE: usize, S>(
+    seed: &[u8; 32],
+) -> Array<Array<Poly<SIZE, false>, DIM>, DIM>
+where
+    S: Symmetric,
+{
+    rows(|i| matrix_row::<DIM, SIZE, S>(seed, i))
+}
+
+// the one byte domain separator of `PRF`, the nonces are at most `2 * DIM`,
+// so it never fails for the parameter sets
+fn nonce(i: usize) -> u8 {
+    u8::try_from(i).unwrap_or_else(|_| panic!("the nonce {i} does not fit in one byte"))
+}
+
+// `(0..DIM).map(f).collect()`, the rows are independent, so under the `rayon` feature
+// they are computed in parallel, the result is the same
+#[cfg(not(feature = "rayon"))]
+fn rows<T, F, const DIM: usize>(f: F) -> Array<T, DIM>
+where
+    T: Copy,
+    F: Fn(usize) -> T,
+{
+    (0..DIM).map(f).collect()
+}
+
+#[cfg(feature = "rayon")]
+fn rows<T, F, const DIM: usize>(f: F) -> Array<T, DIM>
+where
+    T: Copy + Send,
+    F: Fn(usize) -> T + Sync + Send,
+{
+    Array::par_new(f)
+}
+
+// the row `i` of `matrix_from_seed`
+fn matrix_row<const DIM: usize, const SIZE: usize, S>(
+    seed: &[u8; 32],
+    i: usize,
+) -> Array<Poly<SIZE, false>, DIM>
+where
+    S: Symmetric,
+{
+    (0..DIM)
+        .map(|j| Poly::get_uniform::<S>(seed, i, j))
+        .collect()
+}
+
+fn poly_vector_from_bytes<const DIM: usize, const SIZE: usize>(
+    bytes: &[u8],
+) -> Result<Array<Poly<SIZE, false>, DIM>, DecodeError> {
+    Array::try_new(&mut bytes.chunks(12 * SIZE).map(Poly::from_bytes_checked))
 }
 
 impl<const DIM: usize, const SIZE: usize> SecretKey<DIM, SIZE> {
-    pub fn from_bytes(bytes: &[u8]) -> Self {
-        let poly_vector = bytes.chunks(12 * SIZE).map(Poly::from_bytes).collect();
+    /// Recomputes `A s + e`. The matrix `A` is sampled from the public `seed`,
+    /// the error `e` is sampled from the `noise_seed`, the same the secret key was sampled from.
+    pub fn derive_public<C, S>(
+        &self,
+        seed: &[u8; 32],
+        noise_seed: &[u8; 32],
+    ) -> PublicKey<DIM, SIZE>
+    where
+        C: Config<SIZE>,
+        Poly<SIZE, false>: PolyMul,
+        Poly<SIZE, true>: Ntt<Output = Poly<SIZE, false>>,
+        S: Symmetric,
+    {
+        let a = matrix_from_seed::<DIM, SIZE, S>(seed);
+
+        let pk_pv = rows(|i| {
+            let row = (0..DIM).map(|j| &a[j][i]);
+            let mut p = Poly::mul_fold_montgomery(row, self.poly_vector.iter()).montgomery_reduce();
+            let e = C::get_noise::<S>(noise_seed, nonce(DIM + i)).ntt();
+            p += &e;
+            p.barrett_reduce()
+        });
+
+        PublicKey {
+            poly_vector: pk_pv,
+            #[cfg(not(feature = "compact-public-key"))]
+            matrix: a,
+            seed: *seed,
+        }
+    }
+
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        check_length(bytes, 12 * SIZE * DIM)?;
+        let poly_vector = poly_vector_from_bytes(bytes)?;
+
+        Ok(SecretKey { poly_vector })
+    }
+
+    pub fn to_bytes<U>(&self, update: &mut U)
+    where
+        U: Update,
+    {
+        for p in &self.poly_vector {
+            p.to_bytes(update);
+        }
+    }
+}
 
-        SecretKey { poly_vector }
+impl<const DIM: usize, const SIZE: usize> TryFrom<&[u8]> for SecretKey<DIM, SIZE> {
+    type Error = DecodeError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(bytes)
     }
 }
 
 impl<const DIM: usize, const SIZE: usize> PublicKey<DIM, SIZE> {
-    pub fn from_bytes(bytes: &[u8]) -> Self {
-        let pk_pv = bytes
-            .chunks(12 * SIZE)
-            .take(DIM)
-            .map(Poly::from_bytes)
-            .collect();
-        let seed = bytes[(12 * SIZE * DIM)..].try_into().unwrap();
-        let a = (0..DIM)
-            .map(|i| {
-                (0..DIM)
-                    .map(|j| Poly::get_uniform::<Shake128>(&seed, i, j))
-                    .collect()
-            })
-            .collect();
+    pub fn try_from_bytes<S>(bytes: &[u8]) -> Result<Self, DecodeError>
+    where
+        S: Symmetric,
+    {
+        check_length(bytes, 12 * SIZE * DIM + 32)?;
+        let pk_pv = poly_vector_from_bytes(&bytes[..(12 * SIZE * DIM)])?;
+        let mut seed = [0; 32];
+        seed.clone_from_slice(&bytes[(12 * SIZE * DIM)..]);
+
+        Ok(Self::from_parts::<S>(pk_pv, seed))
+    }
 
+    // could be `const` only if there is no matrix to sample
+    #[cfg_attr(feature = "compact-public-key", allow(clippy::missing_const_for_fn))]
+    fn from_parts<S>(poly_vector: Array<Poly<SIZE, false>, DIM>, seed: [u8; 32]) -> Self
+    where
+        S: Symmetric,
+    {
         PublicKey {
-            poly_vector: pk_pv,
-            matrix: a,
+            poly_vector,
+            #[cfg(not(feature = "compact-public-key"))]
+            matrix: matrix_from_seed::<DIM, SIZE, S>(&seed),
             seed,
         }
     }
 
+    #[cfg(not(feature = "compact-public-key"))]
+    #[inline]
+    fn matrix_row<S>(&self, i: usize) -> Array<Poly<SIZE, false>, DIM>
+    where
+        S: Symmetric,
+    {
+        self.matrix[i]
+    }
+
+    #[cfg(feature = "compact-public-key")]
+    #[inline]
+    fn matrix_row<S>(&self, i: usize) -> Array<Poly<SIZE, false>, DIM>
+    where
+        S: Symmetric,
+    {
+        matrix_row::<DIM, SIZE, S>(&self.seed, i)
+    }
+
     pub fn to_bytes<U>(&self, update: &mut U)
     where
         U: Update,
     {
-        for p in self.poly_vector.as_ref() {
+        for p in &self.poly_vector {
             p.to_bytes(update);
         }
         update.update(&self.seed);
     }
+
+    pub fn byte_iter(&self) -> impl Iterator<Item = u8> + '_ {
+        self.poly_vector
+            .iter()
+            .flat_map(Poly::byte_iter)
+            .chain(self.seed.iter().copied())
+    }
+}
+
+/// Decodes the public key from the bytes given in chunks of any length,
+/// the result is the same as `PublicKey::try_from_bytes` gives.
+pub struct PublicKeyDecoder<const DIM: usize, const SIZE: usize> {
+    poly_vector: Array<Poly<SIZE, false>, DIM>,
+    seed: [u8; 32],
+    // the bytes of the incomplete block of 8 coefficients
+    block: [u8; 12],
+    length: usize,
+    error: Option<DecodeError>,
+}
+
+impl<const DIM: usize, const SIZE: usize> Default for PublicKeyDecoder<DIM, SIZE> {
+    fn default() -> Self {
+        PublicKeyDecoder {
+            poly_vector: (0..DIM).map(|_| Poly::zero()).collect(),
+            seed: [0; 32],
+            block: [0; 12],
+            length: 0,
+            error: None,
+        }
+    }
+}
+
+impl<const DIM: usize, const SIZE: usize> Update for PublicKeyDecoder<DIM, SIZE> {
+    fn update(&mut self, data: &[u8]) {
+        let vector_len = 12 * SIZE * DIM;
+        for &b in data {
+            let position = self.length;
+            self.length += 1;
+            if position < vector_len {
+                self.block[position % 12] = b;
+                if position % 12 == 11 {
+                    let index = position / 12;
+                    match PolyBlock::from_bytes_checked(&self.block) {
+                        Ok(block) => {
+                            let poly = &mut self.poly_vector[index / SIZE];
+                            for j in 0..8 {
+                                poly[(index % SIZE) * 8 + j] = block[j];
+                            }
+                        }
+                        Err(err) => {
+                            self.error.get_or_insert(err);
+                        }
+                    }
+                }
+            } else if position < vector_len + 32 {
+                self.seed[position - vector_len] = b;
+            }
+        }
+    }
+}
+
+impl<const DIM: usize, const SIZE: usize> PublicKeyDecoder<DIM, SIZE> {
+    // could be `const` only if there is no matrix to sample
+    #[cfg_attr(feature = "compact-public-key", allow(clippy::missing_const_for_fn))]
+    pub fn finish<S>(self) -> Result<PublicKey<DIM, SIZE>, DecodeError>
+    where
+        S: Symmetric,
+    {
+        let expected = 12 * SIZE * DIM + 32;
+        if self.length != expected {
+            return Err(DecodeError::UnexpectedLength {
+                expected,
+                got: self.length,
+            });
+        }
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+
+        Ok(PublicKey::from_parts::<S>(self.poly_vector, self.seed))
+    }
+}
+
+// the matrix is sampled with the standard primitives
+impl<const DIM: usize, const SIZE: usize> TryFrom<&[u8]> for PublicKey<DIM, SIZE> {
+    type Error = DecodeError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes::<Shake>(bytes)
+    }
 }
 
 impl<const DIM: usize, const SIZE: usize> CipherText<DIM, SIZE>
 where
     Dim<DIM>: Config<SIZE>,
 {
-    pub fn from_bytes(bytes: &[u8]) -> Self {
-        let v = <Dim<DIM> as Config<SIZE>>::COMPRESSED_SIZE;
-        CipherText {
-            poly_vector: bytes
-                .chunks(v)
-                .take(DIM)
-                .map(<Dim<DIM> as Config<SIZE>>::decompress_vec)
-                .collect(),
-            poly: <Dim<DIM> as Config<SIZE>>::decompress(&bytes[(v * DIM)..]),
-        }
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::try_from_bytes_with::<Dim<DIM>>(bytes)
     }
 
     pub fn to_bytes<U>(&self, update: &mut U)
     where
         U: Update,
     {
-        for p in self.poly_vector.as_ref() {
-            <Dim<DIM> as Config<SIZE>>::compress_vec(p, update);
+        self.to_bytes_with::<Dim<DIM>, U>(update);
+    }
+
+    pub fn byte_iter(&self) -> impl Iterator<Item = u8> + '_ {
+        self.poly_vector
+            .iter()
+            .flat_map(<Dim<DIM> as Config<SIZE>>::compress_vec_iter)
+            .chain(<Dim<DIM> as Config<SIZE>>::compress_iter(&self.poly))
+    }
+}
+
+// the encoding and the comparison depend on the compression parameters
+impl<const DIM: usize, const SIZE: usize> CipherText<DIM, SIZE> {
+    pub fn try_from_bytes_with<C>(bytes: &[u8]) -> Result<Self, DecodeError>
+    where
+        C: Config<SIZE>,
+    {
+        let v = C::COMPRESSED_SIZE;
+        let w = C::COMPRESSED_POLY_SIZE;
+        check_length(bytes, v * DIM + w)?;
+        Ok(CipherText {
+            poly_vector: bytes.chunks(v).take(DIM).map(C::decompress_vec).collect(),
+            poly: C::decompress(&bytes[(v * DIM)..]),
+        })
+    }
+
+    pub fn to_bytes_with<C, U>(&self, update: &mut U)
+    where
+        C: Config<SIZE>,
+        U: Update,
+    {
+        for p in &self.poly_vector {
+            C::compress_vec(p, update);
+        }
+        C::compress(&self.poly, update);
+    }
+
+    pub fn ct_eq_with<C>(&self, other: &Self) -> Choice
+    where
+        C: Config<SIZE>,
+    {
+        let mut x = 1u8;
+        for i in 0..DIM {
+            for j in 0..(SIZE * 8) {
+                let flag = C::compare_vec(&self.poly_vector[i][j], &other.poly_vector[i][j]);
+                x &= flag.unwrap_u8();
+            }
+        }
+        for j in 0..(SIZE * 8) {
+            let flag = C::compare(&self.poly[j], &other.poly[j]);
+            x &= flag.unwrap_u8();
+        }
+
+        x.into()
+    }
+}
+
+impl<const DIM: usize, const SIZE: usize> TryFrom<&[u8]> for CipherText<DIM, SIZE>
+where
+    Dim<DIM>: Config<SIZE>,
+{
+    type Error = DecodeError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(bytes)
+    }
+}
+
+impl<const DIM: usize, const SIZE: usize> CipherText<DIM, SIZE> {
+    pub fn prepared(&self) -> PreparedCipherText<DIM, SIZE>
+    where
+        Poly<SIZE, true>: Ntt<Output = Poly<SIZE, false>>,
+    {
+        PreparedCipherText {
+            poly_vector: self.poly_vector.iter().map(|b| b.ntt()).collect(),
+            poly: self.poly,
         }
-        <Dim<DIM> as Config<SIZE>>::compress(&self.poly, update);
+    }
+
+    /// The vector `b` and the polynomial `v`, uncompressed if the cipher text is
+    /// the result of `encapsulate`, decompressed if it is decoded.
+    #[cfg(feature = "bench")]
+    pub const fn parts(&self) -> (&Array<Poly<SIZE, true>, DIM>, &Poly<SIZE, true>) {
+        (&self.poly_vector, &self.poly)
     }
 }
 
@@ -243,21 +666,116 @@ where
 {
     #[inline]
     fn ct_eq(&self, other: &Self) -> Choice {
-        let mut x = 1u8;
-        for i in 0..DIM {
-            for j in 0..SIZE {
-                let flag = <Dim<DIM> as Config<SIZE>>::compare_vec(
-                    &self.poly_vector[i][j],
-                    &other.poly_vector[i][j],
-                );
-                x &= flag.unwrap_u8();
+        self.ct_eq_with::<Dim<DIM>>(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sha3::{
+        Sha3_512,
+        digest::{Update, FixedOutput},
+    };
+
+    use super::{
+        Array, Poly, PolyMul, Ntt, Message, Dim, Config, Shake, split, split_zeroizing, key_pair,
+        encapsulate,
+    };
+
+    type Matrix = Array<Array<Poly<32, false>, 3>, 3>;
+
+    #[test]
+    #[should_panic(expected = "the nonce 256 does not fit in one byte")]
+    fn nonce_one_byte() {
+        assert_eq!(super::nonce(255), 255);
+        super::nonce(256);
+    }
+
+    #[test]
+    fn split_zeroizing_halves() {
+        let mut x = [0; 64];
+        x.iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+        let (a, b) = split_zeroizing(x);
+        assert_eq!((*a, *b), split(x));
+    }
+
+    type Vector<const B: bool> = Array<Poly<32, B>, 3>;
+
+    // the specification samples `A[i][j]` from `XOF(rho, j, i)`,
+    // sampled here independently of `matrix_row`
+    fn spec_matrix(seed: &[u8; 32]) -> Matrix {
+        (0..3)
+            .map(|i| {
+                (0..3)
+                    .map(|j| Poly::get_uniform::<Shake>(seed, j, i))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn transpose(a: &Matrix) -> Matrix {
+        (0..3).map(|i| (0..3).map(|j| a[j][i]).collect()).collect()
+    }
+
+    #[test]
+    fn matrix_from_seed() {
+        let (_, pk) = key_pair::<3, 32, Dim<3>, Shake>(&[1; 32]);
+        let a = super::matrix_from_seed::<3, 32, Shake>(&pk.seed);
+        // the row the public key stores, or samples with `compact-public-key`
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!(pk.matrix_row::<Shake>(i)[j] == a[i][j]);
             }
         }
-        for j in 0..SIZE {
-            let flag = <Dim<DIM> as Config<SIZE>>::compare(&self.poly[j], &other.poly[j]);
-            x &= flag.unwrap_u8();
-        }
+    }
 
-        x.into()
+    // a regression here passes the self consistency tests, but breaks interoperability
+    #[test]
+    fn matrix_orientation() {
+        let d = [1; 32];
+        let (sk, pk) = key_pair::<3, 32, Dim<3>, Shake>(&d);
+        let (rho, sigma) = split(Sha3_512::default().chain(d).finalize_fixed().into());
+        let a = spec_matrix(&rho);
+
+        // the key generation computes `t = A s + e`
+        let t = |m: &Matrix| -> Vector<false> {
+            (0..3)
+                .map(|i| {
+                    let mut p = Poly::mul_fold_montgomery(m[i].iter(), sk.poly_vector.iter())
+                        .montgomery_reduce();
+                    p += &<Dim<3> as Config<32>>::get_noise::<Shake>(&sigma, 3 + i as u8).ntt();
+                    p.barrett_reduce()
+                })
+                .collect()
+        };
+        assert!(pk.poly_vector == t(&a));
+        assert!(pk.poly_vector != t(&transpose(&a)));
+
+        // the encapsulation computes `u = A^T r + e1`
+        let coins = [3; 32];
+        let ct = encapsulate::<3, 32, Dim<3>, Shake>(&coins, &Message([4; 32]), &pk);
+        let r: Vector<false> = (0..3)
+            .map(|j| <Dim<3> as Config<32>>::get_noise::<Shake>(&coins, j as u8).ntt())
+            .collect();
+        let u = |m: &Matrix| -> Vector<true> {
+            (0..3)
+                .map(|i| {
+                    let mut p = Poly::mul_fold_montgomery(m[i].iter(), r.iter()).ntt();
+                    p += &<Dim<3> as Config<32>>::get_noise_e2::<Shake>(&coins, 3 + i as u8);
+                    p.barrett_reduce()
+                })
+                .collect()
+        };
+        assert!(ct.poly_vector == u(&transpose(&a)));
+        assert!(ct.poly_vector != u(&a));
+    }
+
+    // the parallel rows under the `rayon` feature are in order, keygen is deterministic
+    #[test]
+    fn rows() {
+        let a = super::rows::<_, _, 4>(|i| Poly::<32, false>::get_uniform::<Shake>(&[7; 32], i, 0));
+        for (i, p) in a.iter().enumerate() {
+            assert!(*p == Poly::get_uniform::<Shake>(&[7; 32], i, 0));
+        }
     }
 }
@@ -1,24 +1,120 @@
-use core::cmp::Ordering;
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+};
 
-use rand::{prelude::Distribution, distributions::Standard};
+use rand::{prelude::Distribution, distributions::Standard, Rng, RngCore, CryptoRng};
 use sha3::{
-    Sha3_256, Sha3_512, Shake256,
+    Sha3_256, Shake256,
     digest::{Update, FixedOutput, ExtendableOutput, XofReader},
 };
-use subtle::{ConstantTimeEq, ConditionallySelectable};
+use subtle::{Choice, ConstantTimeEq, ConditionallySelectable};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use super::{
+    error::check_length,
     config::{Dim, Config},
-    indcpa::{self, split},
+    indcpa::{self, split, split_zeroizing},
+    poly::Message,
+    symmetric::{Symmetric, Shake},
 };
 
+pub use super::error::{DecodeError, ConsistencyError, WeakSeed};
+
+pub mod mlkem;
+
+mod variant;
+
+pub use self::variant::{Variant, AnySecretKey, AnyPublicKey, AnyCipherText};
+
+mod borrowed;
+
+pub use self::borrowed::{PublicKeyRef, CipherTextRef};
+
+mod pair;
+
+pub use self::pair::KeyPair;
+
+#[cfg(feature = "kyber90s")]
+pub mod kyber90s;
+
+#[cfg(feature = "round2")]
+pub mod round2;
+
+#[cfg(feature = "hybrid")]
+pub mod hybrid;
+
+#[cfg(feature = "unstable-cpa")]
+pub mod cpa;
+
+#[cfg(feature = "kem")]
+mod traits;
+
+#[cfg(feature = "kem")]
+pub use self::traits::SharedSecret;
+
+#[cfg(feature = "serde")]
+mod serde;
+
+#[cfg(feature = "std")]
+mod io;
+
+#[cfg(feature = "std")]
+pub use self::io::WriteUpdate;
+
+#[cfg(feature = "getrandom")]
+mod os;
+
+#[cfg(feature = "getrandom")]
+pub use self::os::{key_pair_os, encapsulate_os};
+
+/// Length of the shared secret.
+pub const SHARED_SECRET_BYTES: usize = 32;
+
+/// Compares two shared secrets in constant time.
+///
+/// Never compare shared secrets with `==`, it returns on the first mismatch,
+/// so the timing tells how many leading bytes are equal.
+#[must_use]
+pub fn shared_secret_eq(a: &[u8; 32], b: &[u8; 32]) -> Choice {
+    a.ct_eq(b)
+}
+
 /// The seed for key pair.
 pub struct KeySeed {
     pub main: [u8; 32],
     pub reject: [u8; 32],
 }
 
+impl KeySeed {
+    /// Length of the serialized seed.
+    pub const BYTES: usize = 64;
+
+    /// Serialize the seed, `main` goes first.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut b = [0; 64];
+        b[..32].clone_from_slice(&self.main);
+        b[32..].clone_from_slice(&self.reject);
+        b
+    }
+
+    /// Deserialize the seed, `main` goes first, see `to_bytes`.
+    #[must_use]
+    pub fn from_bytes(b: &[u8; 64]) -> Self {
+        let (main, reject) = split(*b);
+        KeySeed { main, reject }
+    }
+
+    /// Either half is all zero, constant time. Only the literal zero is detected,
+    /// a structured seed, e.g. of a test vector, is not.
+    #[must_use]
+    pub fn is_weak(&self) -> Choice {
+        self.main.ct_eq(&[0; 32]) | self.reject.ct_eq(&[0; 32])
+    }
+}
+
 impl Distribution<KeySeed> for Standard {
     fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> KeySeed {
         KeySeed {
@@ -29,7 +125,7 @@ impl Distribution<KeySeed> for Standard {
 }
 
 /// The secret key. Intended to keep only in RAM, do not store persistently.
-/// Store the seed instead.
+/// Store the seed instead, see `KeySeed::to_bytes` and `SecretKey::from_seed`.
 #[derive(Clone, Zeroize, ZeroizeOnDrop)]
 pub struct SecretKey<const DIM: usize> {
     inner: indcpa::SecretKey<DIM, 32>,
@@ -44,9 +140,30 @@ pub struct PublicKey<const DIM: usize> {
     hash: [u8; 32],
 }
 
+impl<const DIM: usize> ConstantTimeEq for SecretKey<DIM> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.inner.ct_eq(&other.inner) & self.reject.ct_eq(&other.reject)
+    }
+}
+
+impl<const DIM: usize> PartialEq for SecretKey<DIM> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const DIM: usize> Eq for SecretKey<DIM> {}
+
+// compares the keys itself rather than the hashes
+impl<const DIM: usize> ConstantTimeEq for PublicKey<DIM> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.inner.ct_eq(&other.inner)
+    }
+}
+
 impl<const DIM: usize> PartialEq for PublicKey<DIM> {
     fn eq(&self, other: &Self) -> bool {
-        self.hash.eq(&other.hash)
+        self.ct_eq(other).into()
     }
 }
 
@@ -54,7 +171,7 @@ impl<const DIM: usize> Eq for PublicKey<DIM> {}
 
 impl<const DIM: usize> PartialOrd for PublicKey<DIM> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.hash.partial_cmp(&other.hash)
+        Some(self.cmp(other))
     }
 }
 
@@ -64,35 +181,194 @@ impl<const DIM: usize> Ord for PublicKey<DIM> {
     }
 }
 
+// the hash of the serialized key determines the key, so equal keys hash equal;
+// a loaded key pair has its stored hash checked, see `try_load_key_pair`
+impl<const DIM: usize> Hash for PublicKey<DIM> {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        self.hash.hash(state);
+    }
+}
+
+// never prints the key material
+impl<const DIM: usize> fmt::Debug for SecretKey<DIM> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey<{DIM}>(<redacted>)")
+    }
+}
+
+impl<const DIM: usize> fmt::Debug for PublicKey<DIM> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PublicKey<{DIM}>({self})")
+    }
+}
+
+// the serialized key in lowercase hex
+impl<const DIM: usize> fmt::Display for PublicKey<DIM> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut writer = HexWriter::new(f);
+        self.to_bytes(&mut writer);
+        writer.result
+    }
+}
+
 /// The encapsulated secret. Use `to_bytes` and `from_bytes` to store or transmit.
+///
+/// Ordered and hashed by the serialized bytes, so it can key a map. The comparison is not
+/// constant time, the equality is.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
 pub struct CipherText<const DIM: usize> {
     inner: indcpa::CipherText<DIM, 32>,
 }
 
+impl<const DIM: usize> fmt::Debug for CipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CipherText<{DIM}>({self})")
+    }
+}
+
+// the serialized cipher text in lowercase hex
+impl<const DIM: usize> fmt::Display for CipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut writer = HexWriter::new(f);
+        self.to_bytes(&mut writer);
+        writer.result
+    }
+}
+
+impl<const DIM: usize> ConstantTimeEq for CipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.inner.ct_eq(&other.inner)
+    }
+}
+
+impl<const DIM: usize> PartialEq for CipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const DIM: usize> Eq for CipherText<DIM> where Dim<DIM>: Config<32> {}
+
+// the order and the hash of the serialized bytes, to index received cipher texts;
+// not constant time by nature, never use them in control flow depending on a secret
+impl<const DIM: usize> PartialOrd for CipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const DIM: usize> Ord for CipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        let mut a = [0; MAX_BYTES];
+        let mut b = [0; MAX_BYTES];
+        self.to_bytes(&mut ArrayWriter::new(&mut a));
+        other.to_bytes(&mut ArrayWriter::new(&mut b));
+        a[..Self::BYTES].cmp(&b[..Self::BYTES])
+    }
+}
+
+impl<const DIM: usize> Hash for CipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        self.to_bytes(&mut HashWriter(state));
+    }
+}
+
+/// The parameter set, use `Kyber512`, `Kyber768` or `Kyber1024`.
+pub struct Kyber<const DIM: usize>;
+
+pub type Kyber512 = Kyber<2>;
+
+pub type Kyber768 = Kyber<3>;
+
+pub type Kyber1024 = Kyber<4>;
+
+/// The cipher text prepared for repeated decapsulation, see `CipherText::prepared`.
+pub struct PreparedCipherText<'a, const DIM: usize> {
+    cipher_text: &'a CipherText<DIM>,
+    inner: indcpa::PreparedCipherText<DIM, 32>,
+}
+
 /// Deserialize a key pair from bytes
 ///
 /// # Panics
 ///
-/// will panic if length of bytes not equal to `768 * DIM + 96`
+/// will panic if length of bytes not equal to `SecretKey::<DIM>::BYTES`
+/// or if some coefficient is not canonical,
+/// or if the stored hash of the public key is not its hash
 #[must_use]
 pub fn load_key_pair<const DIM: usize>(b: &[u8]) -> (SecretKey<DIM>, PublicKey<DIM>) {
+    try_load_key_pair(b).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Deserialize a key pair from bytes
+///
+/// # Errors
+///
+/// will return an error if length of bytes not equal to `SecretKey::<DIM>::BYTES`
+/// or if some coefficient is not canonical,
+/// or if the stored hash of the public key is not its hash
+pub fn try_load_key_pair<const DIM: usize>(
+    b: &[u8],
+) -> Result<(SecretKey<DIM>, PublicKey<DIM>), DecodeError> {
+    try_load_key_pair_with::<DIM, Shake>(b)
+}
+
+fn try_load_key_pair_with<const DIM: usize, S>(
+    b: &[u8],
+) -> Result<(SecretKey<DIM>, PublicKey<DIM>), DecodeError>
+where
+    S: Symmetric,
+{
     let sk_len = 12 * 32 * DIM;
     let pk_len = 12 * 32 * DIM + 32;
     let pk_hash_len = 32;
-    let sk_reject_len = 32;
-    assert_eq!(b.len(), sk_len + pk_len + pk_hash_len + sk_reject_len);
-    (
+    check_length(b, SecretKey::<DIM>::BYTES)?;
+
+    let mut reject = [0; 32];
+    reject.clone_from_slice(&b[(sk_len + pk_len + pk_hash_len)..]);
+    // `Ord` and `Hash` of the public key use the hash, it must be the hash of the key
+    let pk_bytes = &b[sk_len..(sk_len + pk_len)];
+    let hash: [u8; 32] = S::H::default().chain(pk_bytes).finalize_fixed().into();
+    if hash[..] != b[(sk_len + pk_len)..(sk_len + pk_len + pk_hash_len)] {
+        return Err(DecodeError::PublicKeyHashMismatch);
+    }
+    Ok((
         SecretKey {
-            inner: indcpa::SecretKey::from_bytes(&b[..sk_len]),
-            reject: b[(sk_len + pk_len + pk_hash_len)..].try_into().unwrap(),
+            inner: indcpa::SecretKey::try_from_bytes(&b[..sk_len])?,
+            reject,
         },
         PublicKey {
-            inner: indcpa::PublicKey::from_bytes(&b[sk_len..(sk_len + pk_len)]),
-            hash: b[(sk_len + pk_len)..(sk_len + pk_len + pk_hash_len)]
-                .try_into()
-                .unwrap(),
+            inner: indcpa::PublicKey::try_from_bytes::<S>(pk_bytes)?,
+            hash,
         },
-    )
+    ))
 }
 
 /// Creates a key pair from the seed.
@@ -101,13 +377,46 @@ pub fn load_key_pair<const DIM: usize>(b: &[u8]) -> (SecretKey<DIM>, PublicKey<D
 pub fn key_pair<const DIM: usize>(s: KeySeed) -> (SecretKey<DIM>, PublicKey<DIM>)
 where
     Dim<DIM>: Config<32>,
+{
+    key_pair_with::<DIM, Dim<DIM>, Shake>(s)
+}
+
+/// Creates a key pair from the serialized seed, e.g. a part of a larger buffer,
+/// without copying it into an array first.
+///
+/// The first 32 bytes are `main`, the last 32 bytes are `reject`, the layout of
+/// `KeySeed::to_bytes`. The bytes are copied once, into the seed the key generation wipes,
+/// the caller owns the slice and wipes it.
+///
+/// # Errors
+///
+/// will return an error if length of bytes not equal to `KeySeed::BYTES`
+pub fn key_pair_from_slice<const DIM: usize>(
+    b: &[u8],
+) -> Result<(SecretKey<DIM>, PublicKey<DIM>), DecodeError>
+where
+    Dim<DIM>: Config<32>,
+{
+    check_length(b, KeySeed::BYTES)?;
+    let mut main = [0; 32];
+    main.clone_from_slice(&b[..32]);
+    let mut reject = [0; 32];
+    reject.clone_from_slice(&b[32..]);
+    Ok(key_pair(KeySeed { main, reject }))
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn key_pair_with<const DIM: usize, C, S>(s: KeySeed) -> (SecretKey<DIM>, PublicKey<DIM>)
+where
+    C: Config<32>,
+    S: Symmetric,
 {
     let KeySeed { mut main, reject } = s;
 
-    let (inner_sk, inner) = indcpa::key_pair(&main);
+    let (inner_sk, inner) = indcpa::key_pair::<DIM, 32, C, S>(&main);
     main.zeroize();
 
-    let mut sha = Sha3_256::default();
+    let mut sha = S::H::default();
     inner.to_bytes(&mut sha);
     let hash = sha.finalize_fixed().into();
 
@@ -120,43 +429,218 @@ where
     )
 }
 
+/// Creates a key pair from the seed, rejects the all zero seed, see `KeySeed::is_weak`.
+///
+/// # Errors
+///
+/// will return an error if either half of the seed is all zero
+pub fn try_key_pair<const DIM: usize>(
+    s: KeySeed,
+) -> Result<(SecretKey<DIM>, PublicKey<DIM>), WeakSeed>
+where
+    Dim<DIM>: Config<32>,
+{
+    if bool::from(s.is_weak()) {
+        Err(WeakSeed)
+    } else {
+        Ok(key_pair(s))
+    }
+}
+
+/// Creates a key pair from the seed and runs the pairwise consistency test.
+///
+/// As FIPS 140 requires, the test encapsulates to the fresh public key with the seed
+/// derived from its hash and checks the decapsulation gives the same secret.
+///
+/// # Errors
+///
+/// will return an error if the test fails, it indicates a hardware fault or a bug
+pub fn key_pair_checked<const DIM: usize>(
+    s: KeySeed,
+) -> Result<(SecretKey<DIM>, PublicKey<DIM>), ConsistencyError>
+where
+    Dim<DIM>: Config<32>,
+{
+    let (sk, pk) = key_pair(s);
+    let (ct, mut ss) = encapsulate(pk.hash, &pk);
+    let (mut decapsulated, accepted) = decapsulate_checked(&sk, &pk, &ct);
+    let consistent = accepted & shared_secret_eq(&ss, &decapsulated);
+    ss.zeroize();
+    decapsulated.zeroize();
+
+    if bool::from(consistent) {
+        Ok((sk, pk))
+    } else {
+        Err(ConsistencyError)
+    }
+}
+
+/// Creates a key pair from the seed sampled from the random number generator.
+#[must_use]
+pub fn generate<const DIM: usize, R>(rng: &mut R) -> (SecretKey<DIM>, PublicKey<DIM>)
+where
+    Dim<DIM>: Config<32>,
+    R: RngCore + CryptoRng,
+{
+    key_pair(rng.gen())
+}
+
+/// Creates a key pair from the seed sampled from the random number generator,
+/// rejects the all zero seed, a broken generator gives it, see `try_key_pair`.
+///
+/// # Errors
+///
+/// will return an error if either half of the seed is all zero
+pub fn try_generate<const DIM: usize, R>(
+    rng: &mut R,
+) -> Result<(SecretKey<DIM>, PublicKey<DIM>), WeakSeed>
+where
+    Dim<DIM>: Config<32>,
+    R: RngCore + CryptoRng,
+{
+    try_key_pair(rng.gen())
+}
+
 /// Encapsulates the secret using public key of receiver.
 #[must_use]
 pub fn encapsulate<const DIM: usize>(
     seed: [u8; 32],
     public_key: &PublicKey<DIM>,
 ) -> (CipherText<DIM>, [u8; 32])
+where
+    Dim<DIM>: Config<32>,
+{
+    encapsulate_with::<DIM, 32, Dim<DIM>, Shake>(seed, public_key, &[])
+}
+
+/// Encapsulates the secret using public key of receiver, the seed is the next 32 bytes
+/// of `reader`, e.g. a transcript hash stream. The same as `encapsulate` with these bytes.
+///
+/// The reader must fill the buffer, `XofReader::read` cannot report a short read.
+#[must_use]
+pub fn encapsulate_from_xof<const DIM: usize, R>(
+    reader: &mut R,
+    public_key: &PublicKey<DIM>,
+) -> (CipherText<DIM>, [u8; 32])
+where
+    Dim<DIM>: Config<32>,
+    R: XofReader,
+{
+    let mut seed = [0; 32];
+    reader.read(&mut seed);
+    let result = encapsulate(seed, public_key);
+    seed.zeroize();
+    result
+}
+
+/// Encapsulates independent secrets to each of the public keys, for broadcast.
+///
+/// Nothing is shared between the recipients, the seed of each is the next 32 bytes
+/// of `SHAKE256(seed)`, the same as `encapsulate_from_xof` gives. Sharing the message
+/// would let any recipient compute the secrets of the others, and the noise is derived
+/// from the message and the hash of the public key, the recipient recomputes it
+/// to check the cipher text, so it cannot be shared either. The work is the same as
+/// of separate encapsulations, the batch only saves the sampling of the seeds.
+pub fn encapsulate_batch<'a, const DIM: usize>(
+    seed: [u8; 32],
+    public_keys: &'a [&'a PublicKey<DIM>],
+) -> impl Iterator<Item = (CipherText<DIM>, [u8; 32])> + 'a
 where
     Dim<DIM>: Config<32>,
 {
     let mut seed = seed;
-    let mut message = Sha3_256::default().chain(&seed).finalize_fixed().into();
+    let mut reader = Shake256::default().chain(seed).finalize_xof();
     seed.zeroize();
-    let c = Sha3_512::default()
-        .chain(&message)
-        .chain(&public_key.hash)
+    public_keys
+        .iter()
+        .map(move |public_key| encapsulate_from_xof(&mut reader, public_key))
+}
+
+fn encapsulate_with<const DIM: usize, const L: usize, C, S>(
+    seed: [u8; 32],
+    public_key: &PublicKey<DIM>,
+    context: &[u8],
+) -> (CipherText<DIM>, [u8; L])
+where
+    C: Config<32>,
+    S: Symmetric,
+{
+    let mut seed = seed;
+    let message = S::H::default().chain(seed).finalize_fixed().into();
+    seed.zeroize();
+    encapsulate_derand_with::<DIM, L, C, S>(message, public_key, context)
+}
+
+/// Encapsulates the secret using public key of receiver, binds the `context` into the shared
+/// secret, e.g. the deployment or the protocol transcript.
+///
+/// The cipher text is the same as `encapsulate` gives, the empty context gives the same secret.
+/// The receiver must pass the identical context to `decapsulate_with_context`,
+/// otherwise it gets a different secret, nothing signals the mismatch.
+#[must_use]
+pub fn encapsulate_with_context<const DIM: usize>(
+    seed: [u8; 32],
+    public_key: &PublicKey<DIM>,
+    context: &[u8],
+) -> (CipherText<DIM>, [u8; 32])
+where
+    Dim<DIM>: Config<32>,
+{
+    encapsulate_with::<DIM, 32, Dim<DIM>, Shake>(seed, public_key, context)
+}
+
+/// Encapsulates the secret using public key of receiver. Deterministic, the `message` is `m`
+/// used as is, without hashing, intended for known answer tests.
+#[must_use]
+pub fn encapsulate_derand<const DIM: usize>(
+    message: [u8; 32],
+    public_key: &PublicKey<DIM>,
+) -> (CipherText<DIM>, [u8; 32])
+where
+    Dim<DIM>: Config<32>,
+{
+    encapsulate_derand_with::<DIM, 32, Dim<DIM>, Shake>(message, public_key, &[])
+}
+
+fn encapsulate_derand_with<const DIM: usize, const L: usize, C, S>(
+    message: [u8; 32],
+    public_key: &PublicKey<DIM>,
+    context: &[u8],
+) -> (CipherText<DIM>, [u8; L])
+where
+    C: Config<32>,
+    S: Symmetric,
+{
+    let mut message = Message(message);
+    let c = S::G::default()
+        .chain(&message.0)
+        .chain(public_key.hash)
         .finalize_fixed();
-    let (mut r, mut noise_seed) = split(c.into());
+    let (r, noise_seed) = split_zeroizing(c.into());
 
-    let inner_ct = indcpa::encapsulate(&noise_seed, &message, &public_key.inner);
-    noise_seed.zeroize();
+    let inner_ct = indcpa::encapsulate::<DIM, 32, C, S>(&noise_seed, &message, &public_key.inner);
     message.zeroize();
 
-    let mut sha = Sha3_256::default();
-    inner_ct.to_bytes(&mut sha);
-    let mut ct_hash = sha.finalize_fixed();
+    let mut sha = S::H::default();
+    inner_ct.to_bytes_with::<C, _>(&mut sha);
+    let mut ct_hash = sha.finalize_fixed().into();
 
-    let mut ss = [0; 32];
-    let mut xof = Shake256::default().chain(&r).chain(&ct_hash).finalize_xof();
-    xof.read(&mut ss);
+    let mut ss = [0; L];
+    S::kdf(&r, &ct_hash, context, &mut ss);
 
-    r.zeroize();
     ct_hash.zeroize();
 
     (CipherText { inner: inner_ct }, ss)
 }
 
 /// Decapsulate the secret from cipher text using secret key.
+///
+/// Implicit rejection: if the cipher text does not match the re-encryption of the decrypted
+/// message, the result is `SHAKE256(z || H(c))` where `z` is the `reject` part of the seed
+/// and `c` is the given cipher text. The selection is constant time, there is no branch
+/// on the result of the comparison.
+///
+/// Use `shared_secret_eq` to compare the result with an expected value, not `==`.
 #[must_use]
 pub fn decapsulate<const DIM: usize>(
     secret_key: &SecretKey<DIM>,
@@ -166,45 +650,364 @@ pub fn decapsulate<const DIM: usize>(
 where
     Dim<DIM>: Config<32>,
 {
-    let mut message = indcpa::decapsulate(&cipher_text.inner, &secret_key.inner);
-    let c = Sha3_512::default()
-        .chain(&message)
-        .chain(&public_key.hash)
+    decapsulate_prepared(secret_key, public_key, &cipher_text.prepared())
+}
+
+/// Decapsulate the secret from prepared cipher text using secret key.
+/// The result is the same as `decapsulate` gives.
+#[must_use]
+pub fn decapsulate_prepared<const DIM: usize>(
+    secret_key: &SecretKey<DIM>,
+    public_key: &PublicKey<DIM>,
+    prepared: &PreparedCipherText<'_, DIM>,
+) -> [u8; 32]
+where
+    Dim<DIM>: Config<32>,
+{
+    decapsulate_prepared_with::<DIM, 32, Dim<DIM>, Shake>(secret_key, public_key, prepared, &[]).0
+}
+
+/// Decapsulate the secret bound to the `context`, see `encapsulate_with_context`.
+/// A different context gives a different secret, as the implicit rejection does.
+#[must_use]
+pub fn decapsulate_with_context<const DIM: usize>(
+    secret_key: &SecretKey<DIM>,
+    public_key: &PublicKey<DIM>,
+    cipher_text: &CipherText<DIM>,
+    context: &[u8],
+) -> [u8; 32]
+where
+    Dim<DIM>: Config<32>,
+{
+    decapsulate_prepared_with::<DIM, 32, Dim<DIM>, Shake>(
+        secret_key,
+        public_key,
+        &cipher_text.prepared(),
+        context,
+    )
+    .0
+}
+
+/// Encapsulates the secret of `L` bytes using public key of receiver, e.g. to key an AEAD
+/// without an extra KDF step.
+///
+/// Non-standard extension, squeezes `L` bytes from the same `SHAKE256(r || H(c))` state,
+/// the first 32 bytes are the secret `encapsulate` gives. The receiver must use
+/// `decapsulate_ss` with the same `L`.
+#[must_use]
+pub fn encapsulate_ss<const DIM: usize, const L: usize>(
+    seed: [u8; 32],
+    public_key: &PublicKey<DIM>,
+) -> (CipherText<DIM>, [u8; L])
+where
+    Dim<DIM>: Config<32>,
+{
+    encapsulate_with::<DIM, L, Dim<DIM>, Shake>(seed, public_key, &[])
+}
+
+/// Decapsulate the secret of `L` bytes, see `encapsulate_ss`. Non-standard extension.
+#[must_use]
+pub fn decapsulate_ss<const DIM: usize, const L: usize>(
+    secret_key: &SecretKey<DIM>,
+    public_key: &PublicKey<DIM>,
+    cipher_text: &CipherText<DIM>,
+) -> [u8; L]
+where
+    Dim<DIM>: Config<32>,
+{
+    decapsulate_prepared_with::<DIM, L, Dim<DIM>, Shake>(
+        secret_key,
+        public_key,
+        &cipher_text.prepared(),
+        &[],
+    )
+    .0
+}
+
+/// Decapsulates each of the cipher texts with the same keys, the secrets go to
+/// `shared_secrets` in order, the same as `decapsulate` gives.
+///
+/// Under the `rayon` feature the cipher texts are decapsulated on the thread pool,
+/// the keys are only read, each secret is written by its own task. The caller owns
+/// the output, so the crate does not allocate, wipe it with `Zeroize` after use.
+///
+/// # Panics
+///
+/// will panic if the lengths of `cipher_texts` and `shared_secrets` are not equal
+pub fn decapsulate_many<const DIM: usize>(
+    secret_key: &SecretKey<DIM>,
+    public_key: &PublicKey<DIM>,
+    cipher_texts: &[CipherText<DIM>],
+    shared_secrets: &mut [[u8; 32]],
+) where
+    Dim<DIM>: Config<32>,
+{
+    assert_eq!(cipher_texts.len(), shared_secrets.len());
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        shared_secrets
+            .par_iter_mut()
+            .zip(cipher_texts.par_iter())
+            .for_each(|(ss, ct)| *ss = decapsulate(secret_key, public_key, ct));
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    for (ss, ct) in shared_secrets.iter_mut().zip(cipher_texts) {
+        *ss = decapsulate(secret_key, public_key, ct);
+    }
+}
+
+/// Decapsulate the secret from cipher text using secret key, also tells if it is accepted.
+///
+/// The shared secret is the same as `decapsulate` gives. The flag is false if the implicit
+/// rejection took place. Intended for metrics, it is constant time,
+/// do not branch on it where the timing matters.
+#[must_use]
+pub fn decapsulate_checked<const DIM: usize>(
+    secret_key: &SecretKey<DIM>,
+    public_key: &PublicKey<DIM>,
+    cipher_text: &CipherText<DIM>,
+) -> ([u8; 32], Choice)
+where
+    Dim<DIM>: Config<32>,
+{
+    decapsulate_prepared_with::<DIM, 32, Dim<DIM>, Shake>(
+        secret_key,
+        public_key,
+        &cipher_text.prepared(),
+        &[],
+    )
+}
+
+fn decapsulate_prepared_with<const DIM: usize, const L: usize, C, S>(
+    secret_key: &SecretKey<DIM>,
+    public_key: &PublicKey<DIM>,
+    prepared: &PreparedCipherText<'_, DIM>,
+    context: &[u8],
+) -> ([u8; L], Choice)
+where
+    C: Config<32>,
+    S: Symmetric,
+{
+    let cipher_text = prepared.cipher_text;
+    let mut message = indcpa::decapsulate_prepared(&prepared.inner, &secret_key.inner);
+    let c = S::G::default()
+        .chain(&message.0)
+        .chain(public_key.hash)
         .finalize_fixed();
-    let (mut r, mut noise_seed) = split(c.into());
+    let (mut r, noise_seed) = split_zeroizing(c.into());
 
-    let inner_ct = indcpa::encapsulate(&noise_seed, &message, &public_key.inner);
-    let flag = inner_ct.ct_eq(&cipher_text.inner);
-    noise_seed.zeroize();
+    let inner_ct = indcpa::encapsulate::<DIM, 32, C, S>(&noise_seed, &message, &public_key.inner);
+    let flag = inner_ct.ct_eq_with::<C>(&cipher_text.inner);
     message.zeroize();
 
-    let mut sha = Sha3_256::default();
-    inner_ct.to_bytes(&mut sha);
-    let mut ct_hash = sha.finalize_fixed();
+    // the hash of the given cipher text, not the re-encrypted one,
+    // they are equal unless rejected
+    let mut sha = S::H::default();
+    cipher_text.inner.to_bytes_with::<C, _>(&mut sha);
+    let mut ct_hash = sha.finalize_fixed().into();
 
-    // TODO:
     secret_key
         .reject
         .iter()
         .zip(r.iter_mut())
         .for_each(|(a, b)| b.conditional_assign(a, !flag));
 
-    let mut ss = [0; 32];
-    let mut xof = Shake256::default().chain(&r).chain(&ct_hash).finalize_xof();
-    xof.read(&mut ss);
+    let mut ss = [0; L];
+    S::kdf(&r, &ct_hash, context, &mut ss);
 
-    r.zeroize();
     ct_hash.zeroize();
 
-    ss
+    (ss, flag)
+}
+
+/// Decrypts the message and measures the noise, to study the decryption failure margin.
+///
+/// Returns the message together with the largest distance of a coefficient of the noisy
+/// message from the nearest multiple of `q / 2`, decryption fails if it is more than `q / 4`.
+/// Not for production use, it is not constant time and exposes the decrypted message.
+#[cfg(feature = "noise-analysis")]
+#[must_use]
+pub fn decapsulate_noisy<const DIM: usize>(
+    secret_key: &SecretKey<DIM>,
+    cipher_text: &CipherText<DIM>,
+) -> ([u8; 32], u16)
+where
+    Dim<DIM>: Config<32>,
+{
+    let (message, noise) = indcpa::decapsulate_noisy(&cipher_text.inner, &secret_key.inner);
+    (message.0, noise)
+}
+
+/// Decrypts the message and measures the margin, the smallest distance of a coefficient
+/// of the noisy message to the decision boundary, to plot its distribution.
+///
+/// Zero margin never happens, a bit flips at the distance of one. About `q / 4` minus
+/// the noise `decapsulate_noisy` gives. Not for production use, it is not constant time
+/// and exposes the decrypted message.
+#[cfg(feature = "testing")]
+#[must_use]
+pub fn decapsulate_margin<const DIM: usize>(
+    secret_key: &SecretKey<DIM>,
+    cipher_text: &CipherText<DIM>,
+) -> ([u8; 32], u16)
+where
+    Dim<DIM>: Config<32>,
+{
+    let (message, margin) = indcpa::decapsulate_margin(&cipher_text.inner, &secret_key.inner);
+    (message.0, margin)
+}
+
+// the longest encoding, Kyber1024 public key and cipher text are both 1568 bytes
+const MAX_BYTES: usize = 1568;
+
+struct ArrayWriter<'a> {
+    buffer: &'a mut [u8],
+    position: usize,
+}
+
+impl<'a> ArrayWriter<'a> {
+    fn new(buffer: &'a mut [u8]) -> Self {
+        ArrayWriter {
+            buffer,
+            position: 0,
+        }
+    }
+}
+
+impl Update for ArrayWriter<'_> {
+    fn update(&mut self, data: &[u8]) {
+        let end = self.position + data.len();
+        self.buffer[self.position..end].clone_from_slice(data);
+        self.position = end;
+    }
+}
+
+// writes the serialized bytes as hex directly into the formatter, without a buffer
+struct HexWriter<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    result: fmt::Result,
+}
+
+impl<'a, 'b> HexWriter<'a, 'b> {
+    fn new(f: &'a mut fmt::Formatter<'b>) -> Self {
+        HexWriter { f, result: Ok(()) }
+    }
+}
+
+impl Update for HexWriter<'_, '_> {
+    fn update(&mut self, data: &[u8]) {
+        if self.result.is_ok() {
+            self.result = data.iter().try_for_each(|b| write!(self.f, "{b:02x}"));
+        }
+    }
+}
+
+// feeds the serialized bytes into the hasher
+struct HashWriter<'a, H>(&'a mut H);
+
+impl<H> Update for HashWriter<'_, H>
+where
+    H: Hasher,
+{
+    fn update(&mut self, data: &[u8]) {
+        self.0.write(data);
+    }
 }
 
+impl<const DIM: usize> SecretKey<DIM> {
+    /// Length of the serialized key pair, see `load_key_pair`.
+    pub const BYTES: usize = 768 * DIM + 96;
+
+    /// Regenerates the secret key from the seed, the same as `key_pair` does.
+    #[must_use]
+    pub fn from_seed(s: KeySeed) -> Self
+    where
+        Dim<DIM>: Config<32>,
+    {
+        key_pair(s).0
+    }
+
+    /// Recomputes the public key. Requires the `main` part of the seed the key was created from,
+    /// the public seed and the noise seed are expanded from it. The key is not checked
+    /// against the seed, a wrong seed gives a meaningless public key.
+    #[must_use]
+    pub fn derive_public(&self, main: &[u8; 32]) -> PublicKey<DIM>
+    where
+        Dim<DIM>: Config<32>,
+    {
+        self.derive_public_with::<Dim<DIM>, Shake>(main)
+    }
+
+    fn derive_public_with<C, S>(&self, main: &[u8; 32]) -> PublicKey<DIM>
+    where
+        C: Config<32>,
+        S: Symmetric,
+    {
+        let c = S::G::default().chain(main).finalize_fixed().into();
+        let (seed, noise_seed) = split_zeroizing(c);
+        let inner = self.inner.derive_public::<C, S>(&seed, &noise_seed);
+
+        let mut sha = S::H::default();
+        inner.to_bytes(&mut sha);
+        let hash = sha.finalize_fixed().into();
+
+        PublicKey { inner, hash }
+    }
+
+    /// Serialize the key pair in the format `load_key_pair` expects.
+    pub fn to_bytes<U>(&self, public_key: &PublicKey<DIM>, buffer: &mut U)
+    where
+        U: Update,
+    {
+        self.inner.to_bytes(buffer);
+        public_key.to_bytes(buffer);
+        buffer.update(&public_key.hash);
+        buffer.update(&self.reject);
+    }
+
+    /// Serialize the key pair in the format `load_key_pair` expects.
+    ///
+    /// # Panics
+    ///
+    /// will panic if `N` not equal to `SecretKey::<DIM>::BYTES`
+    #[must_use]
+    pub fn to_array<const N: usize>(&self, public_key: &PublicKey<DIM>) -> [u8; N] {
+        assert_eq!(N, Self::BYTES);
+        let mut array = [0; N];
+        self.to_bytes(public_key, &mut ArrayWriter::new(&mut array));
+        array
+    }
+}
+
+const PUBLIC_KEY_FINGERPRINT_DOMAIN: &[u8] = b"vru-kyber-pk-fp";
+
 impl<const DIM: usize> PublicKey<DIM> {
+    /// Length of the serialized public key.
+    pub const BYTES: usize = 384 * DIM + 32;
+
+    /// The SHA3-256 hash of the serialized key, `H(pk)` of the Fujisaki-Okamoto transform.
+    ///
+    /// An internal protocol value, do not use it for pinning or display,
+    /// see `fingerprint`.
     #[must_use]
     pub const fn hash(&self) -> [u8; 32] {
         self.hash
     }
 
+    /// The fingerprint of the key for display and pinning, independent of the protocol
+    /// hashing, `SHA3-256("vru-kyber-pk-fp" || pk)`. Stable across releases.
+    #[must_use]
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut sha = Sha3_256::default();
+        sha.update(PUBLIC_KEY_FINGERPRINT_DOMAIN);
+        self.to_bytes(&mut sha);
+        sha.finalize_fixed().into()
+    }
+
     pub fn to_bytes<U>(&self, buffer: &mut U)
     where
         U: Update,
@@ -212,13 +1015,110 @@ impl<const DIM: usize> PublicKey<DIM> {
         self.inner.to_bytes(buffer);
     }
 
+    /// The bytes `to_bytes` gives, computed lazily, for pull based I/O.
+    pub fn byte_iter(&self) -> impl Iterator<Item = u8> + '_ {
+        self.inner.byte_iter()
+    }
+
+    /// Serialize the public key into the array.
+    ///
+    /// # Panics
+    ///
+    /// will panic if `N` not equal to `PublicKey::<DIM>::BYTES`
+    #[must_use]
+    pub fn to_array<const N: usize>(&self) -> [u8; N] {
+        assert_eq!(N, Self::BYTES);
+        let mut array = [0; N];
+        self.to_bytes(&mut ArrayWriter::new(&mut array));
+        array
+    }
+
+    /// Deserialize the public key
+    ///
+    /// # Panics
+    ///
+    /// will panic if length of bytes not equal to `PublicKey::<DIM>::BYTES`
+    /// or if some coefficient is not canonical
     #[must_use]
     pub fn from_bytes(b: &[u8]) -> Self {
-        let hash = Sha3_256::default().chain(b).finalize_fixed().into();
+        Self::try_from_bytes(b).unwrap_or_else(|err| panic!("{err}"))
+    }
 
-        PublicKey {
-            inner: indcpa::PublicKey::from_bytes(b),
-            hash,
+    /// Deserialize the public key. Every 12 bit coefficient must be less than `q`,
+    /// it is the modulus check of FIPS 203: a key passes if and only if it encodes back
+    /// to the same bytes, so the key is not re-encoded.
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `PublicKey::<DIM>::BYTES`
+    /// or if some coefficient is not canonical
+    pub fn try_from_bytes(b: &[u8]) -> Result<Self, DecodeError> {
+        Self::try_from_bytes_with::<Shake>(b)
+    }
+
+    fn try_from_bytes_with<S>(b: &[u8]) -> Result<Self, DecodeError>
+    where
+        S: Symmetric,
+    {
+        let inner = indcpa::PublicKey::try_from_bytes::<S>(b)?;
+        let hash = S::H::default().chain(b).finalize_fixed().into();
+
+        Ok(PublicKey { inner, hash })
+    }
+}
+
+/// Deserializes the public key from the bytes received in chunks, without buffering
+/// the whole key. Feed the bytes with `Update::update`, they are parsed and hashed on the fly.
+#[derive(Default)]
+pub struct PublicKeyBuilder<const DIM: usize> {
+    inner: indcpa::PublicKeyDecoder<DIM, 32>,
+    sha: Sha3_256,
+}
+
+impl<const DIM: usize> Update for PublicKeyBuilder<DIM> {
+    fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+        self.sha.update(data);
+    }
+}
+
+impl<const DIM: usize> PublicKeyBuilder<DIM> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The public key, the same as `PublicKey::try_from_bytes` gives for all the bytes.
+    ///
+    /// # Errors
+    ///
+    /// will return an error if the total length not equal to `PublicKey::<DIM>::BYTES`
+    /// or if some coefficient is not canonical
+    pub fn finish(self) -> Result<PublicKey<DIM>, DecodeError> {
+        let inner = self.inner.finish::<Shake>()?;
+        let hash = self.sha.finalize_fixed().into();
+
+        Ok(PublicKey { inner, hash })
+    }
+}
+
+impl<const DIM: usize> TryFrom<&[u8]> for PublicKey<DIM> {
+    type Error = DecodeError;
+
+    fn try_from(b: &[u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(b)
+    }
+}
+
+// independent of the parameters, also the cipher text of `round2`
+impl<const DIM: usize> CipherText<DIM> {
+    /// Transforms the cipher text to NTT domain once,
+    /// useful to decapsulate the same cipher text with many secret keys.
+    #[must_use]
+    pub fn prepared(&self) -> PreparedCipherText<'_, DIM> {
+        PreparedCipherText {
+            cipher_text: self,
+            inner: self.inner.prepared(),
         }
     }
 }
@@ -227,6 +1127,15 @@ impl<const DIM: usize> CipherText<DIM>
 where
     Dim<DIM>: Config<32>,
 {
+    /// Length of the serialized cipher text.
+    pub const BYTES: usize = <Dim<DIM> as Config<32>>::COMPRESSED_SIZE * DIM
+        + <Dim<DIM> as Config<32>>::COMPRESSED_POLY_SIZE;
+
+    #[cfg(feature = "bench")]
+    pub(crate) const fn inner(&self) -> &indcpa::CipherText<DIM, 32> {
+        &self.inner
+    }
+
     pub fn to_bytes<U>(&self, buffer: &mut U)
     where
         U: Update,
@@ -234,10 +1143,131 @@ where
         self.inner.to_bytes(buffer);
     }
 
+    /// The bytes `to_bytes` gives, computed lazily, for pull based I/O.
+    pub fn byte_iter(&self) -> impl Iterator<Item = u8> + '_ {
+        self.inner.byte_iter()
+    }
+
+    /// Serialize the cipher text into the array.
+    ///
+    /// # Panics
+    ///
+    /// will panic if `N` not equal to `CipherText::<DIM>::BYTES`
+    #[must_use]
+    pub fn to_array<const N: usize>(&self) -> [u8; N] {
+        assert_eq!(N, Self::BYTES);
+        let mut array = [0; N];
+        self.to_bytes(&mut ArrayWriter::new(&mut array));
+        array
+    }
+
+    /// Deserialize the cipher text
+    ///
+    /// # Panics
+    ///
+    /// will panic if length of bytes not equal to `CipherText::<DIM>::BYTES`
     #[must_use]
     pub fn from_bytes(b: &[u8]) -> Self {
-        CipherText {
-            inner: indcpa::CipherText::from_bytes(b),
-        }
+        Self::try_from_bytes(b).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Deserialize the cipher text. The length is the only check, any value of
+    /// a compressed coefficient is in range, so any bytes of the length are a cipher text
+    /// and serialize back unchanged.
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `CipherText::<DIM>::BYTES`
+    pub fn try_from_bytes(b: &[u8]) -> Result<Self, DecodeError> {
+        Ok(CipherText {
+            inner: indcpa::CipherText::try_from_bytes(b)?,
+        })
+    }
+}
+
+impl<const DIM: usize> TryFrom<&[u8]> for CipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    type Error = DecodeError;
+
+    fn try_from(b: &[u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(b)
+    }
+}
+
+impl<const DIM: usize> Kyber<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    pub const PUBLIC_KEY_BYTES: usize = PublicKey::<DIM>::BYTES;
+
+    pub const SECRET_KEY_BYTES: usize = SecretKey::<DIM>::BYTES;
+
+    pub const CIPHER_TEXT_BYTES: usize = CipherText::<DIM>::BYTES;
+
+    pub const SHARED_SECRET_BYTES: usize = SHARED_SECRET_BYTES;
+
+    /// Deserialize a key pair from bytes
+    ///
+    /// # Panics
+    ///
+    /// will panic if length of bytes not equal to `SECRET_KEY_BYTES`
+    /// or if some coefficient is not canonical,
+    /// or if the stored hash of the public key is not its hash
+    #[must_use]
+    pub fn load_key_pair(b: &[u8]) -> (SecretKey<DIM>, PublicKey<DIM>) {
+        load_key_pair(b)
+    }
+
+    /// Deserialize a key pair from bytes
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `SECRET_KEY_BYTES`
+    /// or if some coefficient is not canonical,
+    /// or if the stored hash of the public key is not its hash
+    pub fn try_load_key_pair(b: &[u8]) -> Result<(SecretKey<DIM>, PublicKey<DIM>), DecodeError> {
+        try_load_key_pair(b)
+    }
+
+    /// Creates a key pair from the seed.
+    #[must_use]
+    pub fn key_pair(s: KeySeed) -> (SecretKey<DIM>, PublicKey<DIM>) {
+        key_pair(s)
+    }
+
+    /// Creates a key pair from the seed sampled from the random number generator.
+    #[must_use]
+    pub fn generate<R>(rng: &mut R) -> (SecretKey<DIM>, PublicKey<DIM>)
+    where
+        R: RngCore + CryptoRng,
+    {
+        generate(rng)
+    }
+
+    /// Encapsulates the secret using public key of receiver.
+    #[must_use]
+    pub fn encapsulate(seed: [u8; 32], public_key: &PublicKey<DIM>) -> (CipherText<DIM>, [u8; 32]) {
+        encapsulate(seed, public_key)
+    }
+
+    /// Encapsulates the secret using public key of receiver. Deterministic, see `encapsulate_derand`.
+    #[must_use]
+    pub fn encapsulate_derand(
+        message: [u8; 32],
+        public_key: &PublicKey<DIM>,
+    ) -> (CipherText<DIM>, [u8; 32]) {
+        encapsulate_derand(message, public_key)
+    }
+
+    /// Decapsulate the secret from cipher text using secret key.
+    #[must_use]
+    pub fn decapsulate(
+        secret_key: &SecretKey<DIM>,
+        public_key: &PublicKey<DIM>,
+        cipher_text: &CipherText<DIM>,
+    ) -> [u8; 32] {
+        decapsulate(secret_key, public_key, cipher_text)
     }
 }
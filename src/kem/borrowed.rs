@@ -0,0 +1,112 @@
+//! The keys and cipher texts borrowed from the received bytes, for the paths that only
+//! check the bytes and pass them on. Nothing is decoded until `decode`.
+
+use super::{
+    super::{
+        poly::Poly,
+        config::{Dim, Config},
+        error::check_length,
+    },
+    PublicKey, CipherText, DecodeError,
+};
+
+/// The serialized public key, the length is checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKeyRef<'a, const DIM: usize>(&'a [u8]);
+
+/// The serialized cipher text, the length is checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CipherTextRef<'a, const DIM: usize>(&'a [u8]);
+
+impl<'a, const DIM: usize> PublicKeyRef<'a, DIM> {
+    /// Borrows the bytes, does not look at the coefficients, see `validate`.
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `PublicKey::<DIM>::BYTES`
+    pub const fn try_from_bytes(b: &'a [u8]) -> Result<Self, DecodeError> {
+        match check_length(b, PublicKey::<DIM>::BYTES) {
+            Ok(()) => Ok(PublicKeyRef(b)),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Checks the 12 bit coefficients are canonical without building the polynomials
+    /// and without sampling the matrix. If it succeeds, `decode` succeeds.
+    ///
+    /// # Errors
+    ///
+    /// will return an error if some coefficient is not canonical
+    pub fn validate(&self) -> Result<(), DecodeError> {
+        let (poly_vector, _) = self.0.split_at(PublicKey::<DIM>::BYTES - 32);
+        // the same check as `PublicKey::try_from_bytes` does
+        poly_vector
+            .chunks(12 * 32)
+            .try_for_each(Poly::<32, false>::check_bytes)
+    }
+
+    /// The seed of the matrix, the last 32 bytes of the key.
+    #[must_use]
+    pub fn seed(&self) -> &'a [u8] {
+        &self.0[(PublicKey::<DIM>::BYTES - 32)..]
+    }
+
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Decodes the public key, the same as `PublicKey::try_from_bytes`.
+    ///
+    /// # Errors
+    ///
+    /// will return an error if some coefficient is not canonical
+    pub fn decode(&self) -> Result<PublicKey<DIM>, DecodeError> {
+        PublicKey::try_from_bytes(self.0)
+    }
+}
+
+impl<'a, const DIM: usize> TryFrom<&'a [u8]> for PublicKeyRef<'a, DIM> {
+    type Error = DecodeError;
+
+    fn try_from(b: &'a [u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(b)
+    }
+}
+
+impl<'a, const DIM: usize> CipherTextRef<'a, DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    /// Borrows the bytes. The length is the only check, see `CipherText::try_from_bytes`,
+    /// so there is nothing to validate further.
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `CipherText::<DIM>::BYTES`
+    pub fn try_from_bytes(b: &'a [u8]) -> Result<Self, DecodeError> {
+        check_length(b, CipherText::<DIM>::BYTES).map(|()| CipherTextRef(b))
+    }
+
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Decodes the cipher text, never fails, the length is checked.
+    #[must_use]
+    pub fn decode(&self) -> CipherText<DIM> {
+        CipherText::from_bytes(self.0)
+    }
+}
+
+impl<'a, const DIM: usize> TryFrom<&'a [u8]> for CipherTextRef<'a, DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    type Error = DecodeError;
+
+    fn try_from(b: &'a [u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(b)
+    }
+}
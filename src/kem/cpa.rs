@@ -0,0 +1,110 @@
+//! ADVANCED, NOT IND-CCA2 SECURE. The IND-CPA public key encryption Kyber is built on.
+//!
+//! The cipher texts are malleable and chosen cipher texts reveal the secret key,
+//! so the primitive is only secure as a building block, e.g. for a custom variant
+//! of the Fujisaki-Okamoto transform. Use the `kem` module otherwise.
+//! The API is unstable and may change in any release.
+//!
+//! Deserialize the public key with `PublicKey::<DIM>::try_from`.
+
+use sha3::{
+    Sha3_256,
+    digest::{Update, FixedOutput},
+};
+
+use super::super::{
+    config::{Dim, Config},
+    indcpa,
+    poly::Message,
+    symmetric::Shake,
+};
+
+/// The secret key, the secret vector in the NTT domain.
+pub type SecretKey<const DIM: usize> = indcpa::SecretKey<DIM, 32>;
+
+/// The public key.
+pub type PublicKey<const DIM: usize> = indcpa::PublicKey<DIM, 32>;
+
+/// The cipher text, the compressed vector and the compressed polynomial.
+pub type CipherText<const DIM: usize> = indcpa::CipherText<DIM, 32>;
+
+/// Creates a key pair from the seed `d`, `G(d)` gives the seed of the matrix and the noise.
+#[must_use]
+pub fn key_pair<const DIM: usize>(seed: &[u8; 32]) -> (SecretKey<DIM>, PublicKey<DIM>)
+where
+    Dim<DIM>: Config<32>,
+{
+    indcpa::key_pair::<DIM, 32, Dim<DIM>, Shake>(seed)
+}
+
+/// The matrix in the NTT domain as the reference `gen_matrix(seed, transposed)` samples it,
+/// for the known answer harnesses, writes the 12 bit encoding of the polynomials row by row.
+///
+/// `A[i][j]` is `XOF(seed, j, i)`, the key generation multiplies by `A`, the encryption
+/// by the transposed matrix, where `A^T[i][j]` is `XOF(seed, i, j)`. The seed is the last
+/// 32 bytes of the public key.
+pub fn matrix<const DIM: usize, U>(seed: &[u8; 32], transposed: bool, buffer: &mut U)
+where
+    U: Update,
+{
+    // the internal matrix is the transposed one
+    let a = indcpa::matrix_from_seed::<DIM, 32, Shake>(seed);
+    for i in 0..DIM {
+        for j in 0..DIM {
+            let p = if transposed { a[i][j] } else { a[j][i] };
+            p.to_bytes(buffer);
+        }
+    }
+}
+
+/// Encrypts the message, deterministic, all the randomness comes from `coins`.
+/// `K-PKE.Encrypt` of FIPS 203, the encryption `encapsulate` is built on.
+///
+/// Only IND-CPA, the cipher text is malleable, adding to it changes the message predictably.
+/// The same coins must never encrypt two messages, the difference of the messages leaks.
+#[must_use]
+pub fn encrypt<const DIM: usize>(
+    public_key: &PublicKey<DIM>,
+    message: &[u8; 32],
+    coins: &[u8; 32],
+) -> CipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    indcpa::encapsulate::<DIM, 32, Dim<DIM>, Shake>(coins, &Message(*message), public_key)
+}
+
+/// Decrypts the message, `K-PKE.Decrypt` of FIPS 203. Never fails, an invalid cipher text
+/// gives some message, nothing authenticates it.
+#[must_use]
+pub fn decrypt<const DIM: usize>(
+    secret_key: &SecretKey<DIM>,
+    cipher_text: &CipherText<DIM>,
+) -> [u8; 32] {
+    indcpa::decapsulate(cipher_text, secret_key).0
+}
+
+/// Wraps the key for the KEM, computes the hash `H(pk)` the KEM binds into the secret.
+impl<const DIM: usize> From<PublicKey<DIM>> for super::PublicKey<DIM> {
+    fn from(inner: PublicKey<DIM>) -> Self {
+        let mut sha = Sha3_256::default();
+        inner.to_bytes(&mut sha);
+        let hash = sha.finalize_fixed().into();
+        super::PublicKey { inner, hash }
+    }
+}
+
+impl<const DIM: usize> super::PublicKey<DIM> {
+    /// The key of the public key encryption, without the hash.
+    #[must_use]
+    pub const fn inner(&self) -> &PublicKey<DIM> {
+        &self.inner
+    }
+
+    /// The key of the public key encryption, without the hash.
+    #[must_use]
+    pub fn into_inner(self) -> PublicKey<DIM> {
+        // the key wipes itself on drop, so the field cannot be moved out
+        self.inner.clone()
+    }
+}
@@ -0,0 +1,243 @@
+//! Kyber combined with X25519, the shared secret is secure if either of them is secure.
+//!
+//! The shared secret is the first 32 bytes of
+//! `SHAKE256(ss_kyber || ss_x25519 || ct_kyber || ct_x25519)`, where `ct_kyber` is
+//! the serialized Kyber cipher text and `ct_x25519` is the ephemeral X25519 public key.
+//! The serialized public key is the Kyber public key followed by the 32 byte X25519 public key,
+//! the serialized cipher text is `ct_kyber || ct_x25519`.
+
+use rand::{Rng, RngCore, CryptoRng};
+use sha3::{
+    Shake256,
+    digest::{Update, ExtendableOutput, XofReader},
+};
+use x25519_dalek::StaticSecret;
+use zeroize::Zeroize;
+
+use super::{
+    super::{
+        config::{Dim, Config},
+        error::check_length,
+    },
+    KeySeed, SecretKey, PublicKey, CipherText, DecodeError, ArrayWriter,
+};
+
+/// The secret key, both Kyber and X25519.
+#[derive(Clone)]
+pub struct HybridSecretKey<const DIM: usize> {
+    kyber: SecretKey<DIM>,
+    x25519: StaticSecret,
+}
+
+/// The public key, both Kyber and X25519.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HybridPublicKey<const DIM: usize> {
+    kyber: PublicKey<DIM>,
+    x25519: x25519_dalek::PublicKey,
+}
+
+/// The Kyber cipher text and the ephemeral X25519 public key.
+#[derive(Clone)]
+pub struct HybridCipherText<const DIM: usize> {
+    kyber: CipherText<DIM>,
+    x25519: x25519_dalek::PublicKey,
+}
+
+/// Creates a key pair, the Kyber part from the seed, the X25519 part from the secret.
+#[must_use]
+pub fn key_pair<const DIM: usize>(
+    s: KeySeed,
+    x25519: [u8; 32],
+) -> (HybridSecretKey<DIM>, HybridPublicKey<DIM>)
+where
+    Dim<DIM>: Config<32>,
+{
+    let (kyber_sk, kyber_pk) = super::key_pair(s);
+    let x25519_sk = StaticSecret::from(x25519);
+    let x25519_pk = x25519_dalek::PublicKey::from(&x25519_sk);
+
+    (
+        HybridSecretKey {
+            kyber: kyber_sk,
+            x25519: x25519_sk,
+        },
+        HybridPublicKey {
+            kyber: kyber_pk,
+            x25519: x25519_pk,
+        },
+    )
+}
+
+/// Creates a key pair from the seeds sampled from the random number generator.
+#[must_use]
+pub fn generate<const DIM: usize, R>(rng: &mut R) -> (HybridSecretKey<DIM>, HybridPublicKey<DIM>)
+where
+    Dim<DIM>: Config<32>,
+    R: RngCore + CryptoRng,
+{
+    key_pair(rng.gen(), rng.gen())
+}
+
+/// Encapsulates the secret using public key of receiver.
+/// The `seed` is for Kyber, the `ephemeral` is the ephemeral X25519 secret.
+#[must_use]
+pub fn encapsulate<const DIM: usize>(
+    seed: [u8; 32],
+    ephemeral: [u8; 32],
+    public_key: &HybridPublicKey<DIM>,
+) -> (HybridCipherText<DIM>, [u8; 32])
+where
+    Dim<DIM>: Config<32>,
+{
+    let (kyber_ct, mut kyber_ss) = super::encapsulate(seed, &public_key.kyber);
+
+    let ephemeral = StaticSecret::from(ephemeral);
+    let x25519_ct = x25519_dalek::PublicKey::from(&ephemeral);
+    let x25519_ss = ephemeral.diffie_hellman(&public_key.x25519);
+
+    let ct = HybridCipherText {
+        kyber: kyber_ct,
+        x25519: x25519_ct,
+    };
+    let ss = combine(&kyber_ss, x25519_ss.as_bytes(), &ct);
+    kyber_ss.zeroize();
+
+    (ct, ss)
+}
+
+/// Decapsulate the secret from cipher text using secret key.
+/// The Kyber part does implicit rejection, see `kem::decapsulate`.
+#[must_use]
+pub fn decapsulate<const DIM: usize>(
+    secret_key: &HybridSecretKey<DIM>,
+    public_key: &HybridPublicKey<DIM>,
+    cipher_text: &HybridCipherText<DIM>,
+) -> [u8; 32]
+where
+    Dim<DIM>: Config<32>,
+{
+    let mut kyber_ss = super::decapsulate(&secret_key.kyber, &public_key.kyber, &cipher_text.kyber);
+    let x25519_ss = secret_key.x25519.diffie_hellman(&cipher_text.x25519);
+
+    let ss = combine(&kyber_ss, x25519_ss.as_bytes(), cipher_text);
+    kyber_ss.zeroize();
+
+    ss
+}
+
+fn combine<const DIM: usize>(
+    kyber_ss: &[u8; 32],
+    x25519_ss: &[u8; 32],
+    cipher_text: &HybridCipherText<DIM>,
+) -> [u8; 32]
+where
+    Dim<DIM>: Config<32>,
+{
+    let mut xof = Shake256::default().chain(kyber_ss).chain(x25519_ss);
+    cipher_text.to_bytes(&mut xof);
+    let mut ss = [0; 32];
+    xof.finalize_xof().read(&mut ss);
+    ss
+}
+
+impl<const DIM: usize> HybridSecretKey<DIM> {
+    #[must_use]
+    pub const fn kyber(&self) -> &SecretKey<DIM> {
+        &self.kyber
+    }
+}
+
+impl<const DIM: usize> HybridPublicKey<DIM> {
+    /// Length of the serialized public key.
+    pub const BYTES: usize = PublicKey::<DIM>::BYTES + 32;
+
+    #[must_use]
+    pub const fn kyber(&self) -> &PublicKey<DIM> {
+        &self.kyber
+    }
+
+    pub fn to_bytes<U>(&self, buffer: &mut U)
+    where
+        U: Update,
+    {
+        self.kyber.to_bytes(buffer);
+        buffer.update(self.x25519.as_bytes());
+    }
+
+    /// Serialize the public key into the array.
+    ///
+    /// # Panics
+    ///
+    /// will panic if `N` not equal to `HybridPublicKey::<DIM>::BYTES`
+    #[must_use]
+    pub fn to_array<const N: usize>(&self) -> [u8; N] {
+        assert_eq!(N, Self::BYTES);
+        let mut array = [0; N];
+        self.to_bytes(&mut ArrayWriter::new(&mut array));
+        array
+    }
+
+    /// Deserialize the public key
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `HybridPublicKey::<DIM>::BYTES`
+    /// or if some coefficient is not canonical
+    pub fn try_from_bytes(b: &[u8]) -> Result<Self, DecodeError> {
+        check_length(b, Self::BYTES)?;
+        let (kyber, x25519) = b.split_at(PublicKey::<DIM>::BYTES);
+        let mut x25519_bytes = [0; 32];
+        x25519_bytes.clone_from_slice(x25519);
+
+        Ok(HybridPublicKey {
+            kyber: PublicKey::try_from_bytes(kyber)?,
+            x25519: x25519_bytes.into(),
+        })
+    }
+}
+
+impl<const DIM: usize> HybridCipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    /// Length of the serialized cipher text.
+    pub const BYTES: usize = CipherText::<DIM>::BYTES + 32;
+
+    pub fn to_bytes<U>(&self, buffer: &mut U)
+    where
+        U: Update,
+    {
+        self.kyber.to_bytes(buffer);
+        buffer.update(self.x25519.as_bytes());
+    }
+
+    /// Serialize the cipher text into the array.
+    ///
+    /// # Panics
+    ///
+    /// will panic if `N` not equal to `HybridCipherText::<DIM>::BYTES`
+    #[must_use]
+    pub fn to_array<const N: usize>(&self) -> [u8; N] {
+        assert_eq!(N, Self::BYTES);
+        let mut array = [0; N];
+        self.to_bytes(&mut ArrayWriter::new(&mut array));
+        array
+    }
+
+    /// Deserialize the cipher text
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `HybridCipherText::<DIM>::BYTES`
+    pub fn try_from_bytes(b: &[u8]) -> Result<Self, DecodeError> {
+        check_length(b, Self::BYTES)?;
+        let (kyber, x25519) = b.split_at(CipherText::<DIM>::BYTES);
+        let mut x25519_bytes = [0; 32];
+        x25519_bytes.clone_from_slice(x25519);
+
+        Ok(HybridCipherText {
+            kyber: CipherText::try_from_bytes(kyber)?,
+            x25519: x25519_bytes.into(),
+        })
+    }
+}
@@ -0,0 +1,48 @@
+//! Serialization into `std::io::Write`.
+
+use std::io;
+
+use sha3::digest::Update;
+
+/// Adapts `std::io::Write` as the sink for `to_bytes`, e.g. `pk.to_bytes(&mut writer)`.
+///
+/// `Update` cannot fail, so the first error is kept and the rest of the data is skipped,
+/// call `finish` to get the error.
+pub struct WriteUpdate<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W> WriteUpdate<W>
+where
+    W: io::Write,
+{
+    pub const fn new(inner: W) -> Self {
+        WriteUpdate { inner, error: None }
+    }
+
+    /// Returns the writer, or the first error occurred.
+    ///
+    /// # Errors
+    ///
+    /// will return an error if some write failed
+    pub fn finish(self) -> io::Result<W> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.inner),
+        }
+    }
+}
+
+impl<W> Update for WriteUpdate<W>
+where
+    W: io::Write,
+{
+    fn update(&mut self, data: &[u8]) {
+        if self.error.is_none() {
+            if let Err(err) = self.inner.write_all(data) {
+                self.error = Some(err);
+            }
+        }
+    }
+}
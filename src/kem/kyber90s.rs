@@ -0,0 +1,236 @@
+//! Round 3 Kyber-90s, AES-256 in counter mode and SHA-2 instead of SHA-3.
+//!
+//! The keys are distinct types, a key of the standard variant cannot be used here,
+//! the cipher text type is shared.
+
+use core::fmt;
+
+use rand::{Rng, RngCore, CryptoRng};
+use sha3::digest::Update;
+
+use super::{
+    super::{
+        config::{Dim, Config},
+        symmetric::Aes,
+    },
+    KeySeed, CipherText, DecodeError,
+};
+
+/// The secret key of Kyber-90s.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretKey<const DIM: usize>(super::SecretKey<DIM>);
+
+/// The public key of Kyber-90s.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PublicKey<const DIM: usize>(super::PublicKey<DIM>);
+
+impl<const DIM: usize> fmt::Debug for SecretKey<DIM> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<const DIM: usize> fmt::Debug for PublicKey<DIM> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<const DIM: usize> fmt::Display for PublicKey<DIM> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// Deserialize a key pair from bytes
+///
+/// # Panics
+///
+/// will panic if length of bytes not equal to `SecretKey::<DIM>::BYTES`
+/// or if some coefficient is not canonical,
+/// or if the stored hash of the public key is not its hash
+#[must_use]
+pub fn load_key_pair<const DIM: usize>(b: &[u8]) -> (SecretKey<DIM>, PublicKey<DIM>) {
+    try_load_key_pair(b).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Deserialize a key pair from bytes
+///
+/// # Errors
+///
+/// will return an error if length of bytes not equal to `SecretKey::<DIM>::BYTES`
+/// or if some coefficient is not canonical,
+/// or if the stored hash of the public key is not its hash
+pub fn try_load_key_pair<const DIM: usize>(
+    b: &[u8],
+) -> Result<(SecretKey<DIM>, PublicKey<DIM>), DecodeError> {
+    let (sk, pk) = super::try_load_key_pair_with::<DIM, Aes>(b)?;
+    Ok((SecretKey(sk), PublicKey(pk)))
+}
+
+/// Creates a key pair from the seed.
+#[must_use]
+pub fn key_pair<const DIM: usize>(s: KeySeed) -> (SecretKey<DIM>, PublicKey<DIM>)
+where
+    Dim<DIM>: Config<32>,
+{
+    let (sk, pk) = super::key_pair_with::<DIM, Dim<DIM>, Aes>(s);
+    (SecretKey(sk), PublicKey(pk))
+}
+
+/// Creates a key pair from the seed sampled from the random number generator.
+#[must_use]
+pub fn generate<const DIM: usize, R>(rng: &mut R) -> (SecretKey<DIM>, PublicKey<DIM>)
+where
+    Dim<DIM>: Config<32>,
+    R: RngCore + CryptoRng,
+{
+    key_pair(rng.gen())
+}
+
+/// Encapsulates the secret using public key of receiver.
+#[must_use]
+pub fn encapsulate<const DIM: usize>(
+    seed: [u8; 32],
+    public_key: &PublicKey<DIM>,
+) -> (CipherText<DIM>, [u8; 32])
+where
+    Dim<DIM>: Config<32>,
+{
+    super::encapsulate_with::<DIM, 32, Dim<DIM>, Aes>(seed, &public_key.0, &[])
+}
+
+/// Encapsulates the secret using public key of receiver. Deterministic, the `message` is `m`
+/// used as is, without hashing, intended for known answer tests.
+#[must_use]
+pub fn encapsulate_derand<const DIM: usize>(
+    message: [u8; 32],
+    public_key: &PublicKey<DIM>,
+) -> (CipherText<DIM>, [u8; 32])
+where
+    Dim<DIM>: Config<32>,
+{
+    super::encapsulate_derand_with::<DIM, 32, Dim<DIM>, Aes>(message, &public_key.0, &[])
+}
+
+/// Decapsulate the secret from cipher text using secret key.
+/// Implicit rejection gives `SHA-256(z || H(c))`, see `kem::decapsulate`.
+#[must_use]
+pub fn decapsulate<const DIM: usize>(
+    secret_key: &SecretKey<DIM>,
+    public_key: &PublicKey<DIM>,
+    cipher_text: &CipherText<DIM>,
+) -> [u8; 32]
+where
+    Dim<DIM>: Config<32>,
+{
+    super::decapsulate_prepared_with::<DIM, 32, Dim<DIM>, Aes>(
+        &secret_key.0,
+        &public_key.0,
+        &cipher_text.prepared(),
+        &[],
+    )
+    .0
+}
+
+impl<const DIM: usize> SecretKey<DIM> {
+    /// Length of the serialized key pair, see `load_key_pair`.
+    pub const BYTES: usize = super::SecretKey::<DIM>::BYTES;
+
+    /// Recomputes the public key, see `kem::SecretKey::derive_public`.
+    #[must_use]
+    pub fn derive_public(&self, main: &[u8; 32]) -> PublicKey<DIM>
+    where
+        Dim<DIM>: Config<32>,
+    {
+        PublicKey(self.0.derive_public_with::<Dim<DIM>, Aes>(main))
+    }
+
+    /// Serialize the key pair in the format `load_key_pair` expects.
+    pub fn to_bytes<U>(&self, public_key: &PublicKey<DIM>, buffer: &mut U)
+    where
+        U: Update,
+    {
+        self.0.to_bytes(&public_key.0, buffer);
+    }
+
+    /// Serialize the key pair in the format `load_key_pair` expects.
+    ///
+    /// # Panics
+    ///
+    /// will panic if `N` not equal to `SecretKey::<DIM>::BYTES`
+    #[must_use]
+    pub fn to_array<const N: usize>(&self, public_key: &PublicKey<DIM>) -> [u8; N] {
+        self.0.to_array(&public_key.0)
+    }
+}
+
+impl<const DIM: usize> PublicKey<DIM> {
+    /// Length of the serialized public key.
+    pub const BYTES: usize = super::PublicKey::<DIM>::BYTES;
+
+    /// The SHA-256 hash of the serialized key, an internal protocol value,
+    /// see `fingerprint`.
+    #[must_use]
+    pub const fn hash(&self) -> [u8; 32] {
+        self.0.hash
+    }
+
+    /// The fingerprint of the key, see `kem::PublicKey::fingerprint`. The same function
+    /// of the serialized key as in the standard variant.
+    #[must_use]
+    pub fn fingerprint(&self) -> [u8; 32] {
+        self.0.fingerprint()
+    }
+
+    pub fn to_bytes<U>(&self, buffer: &mut U)
+    where
+        U: Update,
+    {
+        self.0.to_bytes(buffer);
+    }
+
+    /// The bytes `to_bytes` gives, computed lazily.
+    pub fn byte_iter(&self) -> impl Iterator<Item = u8> + '_ {
+        self.0.byte_iter()
+    }
+
+    /// Serialize the public key into the array.
+    ///
+    /// # Panics
+    ///
+    /// will panic if `N` not equal to `PublicKey::<DIM>::BYTES`
+    #[must_use]
+    pub fn to_array<const N: usize>(&self) -> [u8; N] {
+        self.0.to_array()
+    }
+
+    /// Deserialize the public key
+    ///
+    /// # Panics
+    ///
+    /// will panic if length of bytes not equal to `PublicKey::<DIM>::BYTES`
+    /// or if some coefficient is not canonical
+    #[must_use]
+    pub fn from_bytes(b: &[u8]) -> Self {
+        Self::try_from_bytes(b).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Deserialize the public key
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `PublicKey::<DIM>::BYTES`
+    /// or if some coefficient is not canonical
+    pub fn try_from_bytes(b: &[u8]) -> Result<Self, DecodeError> {
+        super::PublicKey::try_from_bytes_with::<Aes>(b).map(PublicKey)
+    }
+}
+
+impl<const DIM: usize> TryFrom<&[u8]> for PublicKey<DIM> {
+    type Error = DecodeError;
+
+    fn try_from(b: &[u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(b)
+    }
+}
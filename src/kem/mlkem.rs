@@ -0,0 +1,102 @@
+//! ML-KEM as standardized in FIPS 203. Uses the same key and cipher text types
+//! as the round 3 Kyber functions in the parent module.
+
+use sha3::{
+    Sha3_256, Sha3_512, Shake256,
+    digest::{Update, FixedOutput, ExtendableOutput, XofReader},
+};
+use subtle::{ConstantTimeEq, ConditionallySelectable};
+use zeroize::Zeroize;
+
+use super::{
+    super::{
+        config::{Dim, Config},
+        indcpa::{self, split_zeroizing},
+        poly::Message,
+        symmetric::Shake,
+    },
+    KeySeed, SecretKey, PublicKey, CipherText,
+};
+
+/// Creates a key pair from the seed, `main` is `d` and `reject` is `z`.
+#[must_use]
+#[allow(clippy::needless_pass_by_value)]
+pub fn key_pair<const DIM: usize>(s: KeySeed) -> (SecretKey<DIM>, PublicKey<DIM>)
+where
+    Dim<DIM>: Config<32>,
+{
+    let KeySeed { mut main, reject } = s;
+
+    let (inner_sk, inner) = indcpa::key_pair_separated(&main);
+    main.zeroize();
+
+    let mut sha = Sha3_256::default();
+    inner.to_bytes(&mut sha);
+    let hash = sha.finalize_fixed().into();
+
+    (
+        SecretKey {
+            inner: inner_sk,
+            reject,
+        },
+        PublicKey { inner, hash },
+    )
+}
+
+/// Encapsulates the secret using public key of receiver. The seed is the message `m`.
+#[must_use]
+pub fn encapsulate<const DIM: usize>(
+    seed: [u8; 32],
+    public_key: &PublicKey<DIM>,
+) -> (CipherText<DIM>, [u8; 32])
+where
+    Dim<DIM>: Config<32>,
+{
+    let mut message = Message(seed);
+    let c = Sha3_512::default()
+        .chain(&message.0)
+        .chain(public_key.hash)
+        .finalize_fixed();
+    let (ss, noise_seed) = split_zeroizing(c.into());
+
+    let inner_ct =
+        indcpa::encapsulate::<DIM, 32, Dim<DIM>, Shake>(&noise_seed, &message, &public_key.inner);
+    message.zeroize();
+
+    (CipherText { inner: inner_ct }, *ss)
+}
+
+/// Decapsulate the secret from cipher text using secret key.
+#[must_use]
+pub fn decapsulate<const DIM: usize>(
+    secret_key: &SecretKey<DIM>,
+    public_key: &PublicKey<DIM>,
+    cipher_text: &CipherText<DIM>,
+) -> [u8; 32]
+where
+    Dim<DIM>: Config<32>,
+{
+    let mut message = indcpa::decapsulate(&cipher_text.inner, &secret_key.inner);
+    let c = Sha3_512::default()
+        .chain(&message.0)
+        .chain(public_key.hash)
+        .finalize_fixed();
+    let (mut ss, noise_seed) = split_zeroizing(c.into());
+
+    let inner_ct =
+        indcpa::encapsulate::<DIM, 32, Dim<DIM>, Shake>(&noise_seed, &message, &public_key.inner);
+    let flag = inner_ct.ct_eq(&cipher_text.inner);
+    message.zeroize();
+
+    let mut xof = Shake256::default().chain(secret_key.reject);
+    cipher_text.inner.to_bytes(&mut xof);
+    let mut reject = [0; 32];
+    xof.finalize_xof().read(&mut reject);
+
+    ss.iter_mut()
+        .zip(reject.iter())
+        .for_each(|(a, b)| a.conditional_assign(b, !flag));
+    reject.zeroize();
+
+    *ss
+}
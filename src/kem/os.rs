@@ -0,0 +1,51 @@
+//! Key generation and encapsulation with the entropy of the operating system.
+//!
+//! Deterministic callers, e.g. known answer tests, should use the functions taking the seed.
+
+use zeroize::Zeroize;
+
+use super::{
+    super::config::{Dim, Config},
+    KeySeed, SecretKey, PublicKey, CipherText, key_pair, encapsulate,
+};
+
+fn os_seed() -> [u8; 32] {
+    let mut seed = [0; 32];
+    getrandom::getrandom(&mut seed).unwrap_or_else(|err| panic!("{err}"));
+    seed
+}
+
+/// Creates a key pair from the seed sampled from the operating system, see `key_pair`.
+///
+/// # Panics
+///
+/// will panic if the random number generator of the operating system fails
+#[must_use]
+pub fn key_pair_os<const DIM: usize>() -> (SecretKey<DIM>, PublicKey<DIM>)
+where
+    Dim<DIM>: Config<32>,
+{
+    let mut main = os_seed();
+    let mut reject = os_seed();
+    let pair = key_pair(KeySeed { main, reject });
+    main.zeroize();
+    reject.zeroize();
+    pair
+}
+
+/// Encapsulates the secret using public key of receiver, the seed is sampled
+/// from the operating system, see `encapsulate`.
+///
+/// # Panics
+///
+/// will panic if the random number generator of the operating system fails
+#[must_use]
+pub fn encapsulate_os<const DIM: usize>(public_key: &PublicKey<DIM>) -> (CipherText<DIM>, [u8; 32])
+where
+    Dim<DIM>: Config<32>,
+{
+    let mut seed = os_seed();
+    let result = encapsulate(seed, public_key);
+    seed.zeroize();
+    result
+}
@@ -0,0 +1,109 @@
+//! The secret key bundled with its public key, for the APIs which keep a single object.
+
+use sha3::digest::Update;
+use zeroize::Zeroize;
+
+use super::{
+    super::config::{Dim, Config},
+    KeySeed, SecretKey, PublicKey, CipherText, DecodeError, key_pair, try_load_key_pair,
+    decapsulate,
+};
+
+/// The secret key and its public key, decapsulation needs both.
+///
+/// Keeps the decoded public key in memory, `512 * DIM` bytes of the polynomial vector
+/// and, unless the `compact-public-key` feature is enabled, `512 * DIM * DIM` bytes
+/// of the matrix. The alternative is to keep the secret key and the `main` seed and
+/// recompute the public key with `SecretKey::derive_public`, it costs about a key generation
+/// on each decapsulation.
+// the keys wipe themselves on drop
+#[derive(Clone, Debug, PartialEq, Eq, Zeroize)]
+pub struct KeyPair<const DIM: usize> {
+    secret_key: SecretKey<DIM>,
+    public_key: PublicKey<DIM>,
+}
+
+impl<const DIM: usize> KeyPair<DIM> {
+    /// Length of the serialized key pair, the same as `SecretKey::<DIM>::BYTES`.
+    pub const BYTES: usize = SecretKey::<DIM>::BYTES;
+
+    /// Bundles the keys, the public key must be the one of the secret key.
+    #[must_use]
+    pub const fn new(secret_key: SecretKey<DIM>, public_key: PublicKey<DIM>) -> Self {
+        KeyPair {
+            secret_key,
+            public_key,
+        }
+    }
+
+    /// Creates the key pair from the seed, see `key_pair`.
+    #[must_use]
+    pub fn from_seed(s: KeySeed) -> Self
+    where
+        Dim<DIM>: Config<32>,
+    {
+        let (secret_key, public_key) = key_pair(s);
+        KeyPair::new(secret_key, public_key)
+    }
+
+    /// Recomputes the public key of the secret key, see `SecretKey::derive_public`.
+    #[must_use]
+    pub fn from_secret_key(secret_key: SecretKey<DIM>, main: &[u8; 32]) -> Self
+    where
+        Dim<DIM>: Config<32>,
+    {
+        let public_key = secret_key.derive_public(main);
+        KeyPair::new(secret_key, public_key)
+    }
+
+    /// Deserialize the key pair, the format of `load_key_pair`.
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `KeyPair::<DIM>::BYTES`
+    /// or if some coefficient is not canonical,
+    /// or if the stored hash of the public key is not its hash
+    pub fn try_from_bytes(b: &[u8]) -> Result<Self, DecodeError> {
+        let (secret_key, public_key) = try_load_key_pair(b)?;
+        Ok(KeyPair::new(secret_key, public_key))
+    }
+
+    /// Serialize the key pair in the format `load_key_pair` expects.
+    pub fn to_bytes<U>(&self, buffer: &mut U)
+    where
+        U: Update,
+    {
+        self.secret_key.to_bytes(&self.public_key, buffer);
+    }
+
+    #[must_use]
+    pub const fn secret_key(&self) -> &SecretKey<DIM> {
+        &self.secret_key
+    }
+
+    #[must_use]
+    pub const fn public_key(&self) -> &PublicKey<DIM> {
+        &self.public_key
+    }
+
+    /// Splits the key pair back into the keys.
+    #[must_use]
+    pub fn into_parts(self) -> (SecretKey<DIM>, PublicKey<DIM>) {
+        (self.secret_key, self.public_key)
+    }
+
+    /// Decapsulate the secret from cipher text, see `decapsulate`.
+    #[must_use]
+    pub fn decapsulate(&self, cipher_text: &CipherText<DIM>) -> [u8; 32]
+    where
+        Dim<DIM>: Config<32>,
+    {
+        decapsulate(&self.secret_key, &self.public_key, cipher_text)
+    }
+}
+
+impl<const DIM: usize> From<(SecretKey<DIM>, PublicKey<DIM>)> for KeyPair<DIM> {
+    fn from((secret_key, public_key): (SecretKey<DIM>, PublicKey<DIM>)) -> Self {
+        KeyPair::new(secret_key, public_key)
+    }
+}
@@ -0,0 +1,180 @@
+//! Round 2 Kyber, LEGACY, for the interop with peers which still speak it.
+//!
+//! Kyber768 and Kyber1024 did not change in round 3, the results are the same as `kem` gives.
+//! Kyber512 of round 2 compresses `v` to 3 bits, the cipher text is 736 bytes, and samples
+//! the secret with `eta1 = 2`, see `config::DimR2`. Round 3 changed both for a lower
+//! decryption failure probability and a higher security margin, do not use it for anything new.
+//!
+//! The keys are the ones of `kem`, the serialization is the same. The cipher text is
+//! a distinct type, its serialization and the re-encryption check depend on the parameters.
+//! Note `SecretKey::derive_public` samples the noise of round 3, for a round 2 Kyber512 key
+//! it gives a different public key, keep the one `key_pair` returns.
+
+use core::fmt;
+
+use rand::{Rng, RngCore, CryptoRng};
+use sha3::digest::Update;
+use subtle::{Choice, ConstantTimeEq};
+
+use super::{
+    super::{
+        config::{DimR2, Config},
+        indcpa,
+        symmetric::Shake,
+    },
+    KeySeed, SecretKey, PublicKey, DecodeError, ArrayWriter, HexWriter,
+};
+
+/// The round 2 cipher text.
+#[derive(Clone)]
+pub struct CipherText<const DIM: usize>(super::CipherText<DIM>);
+
+impl<const DIM: usize> fmt::Debug for CipherText<DIM>
+where
+    DimR2<DIM>: Config<32>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CipherText<{DIM}>(")?;
+        let mut writer = HexWriter::new(f);
+        self.to_bytes(&mut writer);
+        writer.result?;
+        write!(f, ")")
+    }
+}
+
+impl<const DIM: usize> ConstantTimeEq for CipherText<DIM>
+where
+    DimR2<DIM>: Config<32>,
+{
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.inner.ct_eq_with::<DimR2<DIM>>(&other.0.inner)
+    }
+}
+
+impl<const DIM: usize> PartialEq for CipherText<DIM>
+where
+    DimR2<DIM>: Config<32>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const DIM: usize> Eq for CipherText<DIM> where DimR2<DIM>: Config<32> {}
+
+/// Creates a key pair from the seed.
+#[must_use]
+pub fn key_pair<const DIM: usize>(s: KeySeed) -> (SecretKey<DIM>, PublicKey<DIM>)
+where
+    DimR2<DIM>: Config<32>,
+{
+    super::key_pair_with::<DIM, DimR2<DIM>, Shake>(s)
+}
+
+/// Creates a key pair from the seed sampled from the random number generator.
+#[must_use]
+pub fn generate<const DIM: usize, R>(rng: &mut R) -> (SecretKey<DIM>, PublicKey<DIM>)
+where
+    DimR2<DIM>: Config<32>,
+    R: RngCore + CryptoRng,
+{
+    key_pair(rng.gen())
+}
+
+/// Encapsulates the secret using public key of receiver.
+#[must_use]
+pub fn encapsulate<const DIM: usize>(
+    seed: [u8; 32],
+    public_key: &PublicKey<DIM>,
+) -> (CipherText<DIM>, [u8; 32])
+where
+    DimR2<DIM>: Config<32>,
+{
+    let (ct, ss) = super::encapsulate_with::<DIM, 32, DimR2<DIM>, Shake>(seed, public_key, &[]);
+    (CipherText(ct), ss)
+}
+
+/// Encapsulates the secret using public key of receiver. Deterministic, the `message` is `m`
+/// used as is, without hashing, intended for known answer tests.
+#[must_use]
+pub fn encapsulate_derand<const DIM: usize>(
+    message: [u8; 32],
+    public_key: &PublicKey<DIM>,
+) -> (CipherText<DIM>, [u8; 32])
+where
+    DimR2<DIM>: Config<32>,
+{
+    let (ct, ss) =
+        super::encapsulate_derand_with::<DIM, 32, DimR2<DIM>, Shake>(message, public_key, &[]);
+    (CipherText(ct), ss)
+}
+
+/// Decapsulate the secret from cipher text using secret key.
+/// The implicit rejection is the same as `kem::decapsulate` does.
+#[must_use]
+pub fn decapsulate<const DIM: usize>(
+    secret_key: &SecretKey<DIM>,
+    public_key: &PublicKey<DIM>,
+    cipher_text: &CipherText<DIM>,
+) -> [u8; 32]
+where
+    DimR2<DIM>: Config<32>,
+{
+    super::decapsulate_prepared_with::<DIM, 32, DimR2<DIM>, Shake>(
+        secret_key,
+        public_key,
+        &cipher_text.0.prepared(),
+        &[],
+    )
+    .0
+}
+
+impl<const DIM: usize> CipherText<DIM>
+where
+    DimR2<DIM>: Config<32>,
+{
+    /// Length of the serialized cipher text.
+    pub const BYTES: usize = <DimR2<DIM> as Config<32>>::COMPRESSED_SIZE * DIM
+        + <DimR2<DIM> as Config<32>>::COMPRESSED_POLY_SIZE;
+
+    pub fn to_bytes<U>(&self, buffer: &mut U)
+    where
+        U: Update,
+    {
+        self.0.inner.to_bytes_with::<DimR2<DIM>, U>(buffer);
+    }
+
+    /// Serialize the cipher text into the array.
+    ///
+    /// # Panics
+    ///
+    /// will panic if `N` not equal to `CipherText::<DIM>::BYTES`
+    #[must_use]
+    pub fn to_array<const N: usize>(&self) -> [u8; N] {
+        assert_eq!(N, Self::BYTES);
+        let mut array = [0; N];
+        self.to_bytes(&mut ArrayWriter::new(&mut array));
+        array
+    }
+
+    /// Deserialize the cipher text
+    ///
+    /// # Panics
+    ///
+    /// will panic if length of bytes not equal to `CipherText::<DIM>::BYTES`
+    #[must_use]
+    pub fn from_bytes(b: &[u8]) -> Self {
+        Self::try_from_bytes(b).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Deserialize the cipher text, the length is the only check.
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `CipherText::<DIM>::BYTES`
+    pub fn try_from_bytes(b: &[u8]) -> Result<Self, DecodeError> {
+        Ok(CipherText(super::CipherText {
+            inner: indcpa::CipherText::try_from_bytes_with::<DimR2<DIM>>(b)?,
+        }))
+    }
+}
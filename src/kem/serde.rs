@@ -0,0 +1,221 @@
+//! Implementation of `Serialize` and `Deserialize` from `serde` crate.
+//! Binary formats get the canonical encoding as bytes, human readable formats get it as hex.
+
+use core::{fmt, marker::PhantomData};
+
+use ::serde::{
+    de::{self, Visitor, SeqAccess},
+    Serialize, Serializer, Deserialize, Deserializer,
+};
+use zeroize::Zeroize;
+
+use super::{
+    super::config::{Dim, Config},
+    DecodeError, PublicKey, CipherText, ArrayWriter, MAX_BYTES,
+};
+
+#[cfg(feature = "serde-secret")]
+use sha3::digest::Update;
+
+#[cfg(feature = "serde-secret")]
+use super::{super::indcpa, SecretKey};
+
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{b:02x}"))
+    }
+}
+
+fn serialize<S, F>(len: usize, write: F, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    F: FnOnce(&mut ArrayWriter<'_>),
+{
+    let mut buffer = [0; MAX_BYTES];
+    write(&mut ArrayWriter::new(&mut buffer[..len]));
+    let result = if serializer.is_human_readable() {
+        serializer.collect_str(&Hex(&buffer[..len]))
+    } else {
+        serializer.serialize_bytes(&buffer[..len])
+    };
+    buffer.iter_mut().for_each(Zeroize::zeroize);
+    result
+}
+
+struct BytesVisitor<T, F> {
+    len: usize,
+    read: F,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T, F> BytesVisitor<T, F>
+where
+    F: FnOnce(&[u8]) -> Result<T, DecodeError>,
+{
+    fn read<E>(self, buffer: &mut [u8; MAX_BYTES]) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        let result = (self.read)(&buffer[..self.len]).map_err(E::custom);
+        buffer.iter_mut().for_each(Zeroize::zeroize);
+        result
+    }
+}
+
+impl<'de, T, F> Visitor<'de> for BytesVisitor<T, F>
+where
+    F: FnOnce(&[u8]) -> Result<T, DecodeError>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{} bytes or a hex string", self.len)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        const fn nibble(c: u8) -> Option<u8> {
+            match c {
+                b'0'..=b'9' => Some(c - b'0'),
+                b'a'..=b'f' => Some(c - b'a' + 10),
+                b'A'..=b'F' => Some(c - b'A' + 10),
+                _ => None,
+            }
+        }
+
+        if v.len() != self.len * 2 {
+            return Err(E::invalid_length(v.len() / 2, &self));
+        }
+        let mut buffer = [0; MAX_BYTES];
+        for (b, pair) in buffer.iter_mut().zip(v.as_bytes().chunks(2)) {
+            match (nibble(pair[0]), nibble(pair[1])) {
+                (Some(hi), Some(lo)) => *b = (hi << 4) | lo,
+                _ => return Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+            }
+        }
+        self.read(&mut buffer)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        if v.len() != self.len {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+        let mut buffer = [0; MAX_BYTES];
+        buffer[..self.len].clone_from_slice(v);
+        self.read(&mut buffer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut buffer = [0; MAX_BYTES];
+        for (i, b) in buffer[..self.len].iter_mut().enumerate() {
+            *b = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(de::Error::invalid_length(self.len + 1, &self));
+        }
+        self.read(&mut buffer)
+    }
+}
+
+fn deserialize<'de, D, T, F>(len: usize, read: F, deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    F: FnOnce(&[u8]) -> Result<T, DecodeError>,
+{
+    let visitor = BytesVisitor {
+        len,
+        read,
+        phantom_data: PhantomData,
+    };
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(visitor)
+    } else {
+        deserializer.deserialize_bytes(visitor)
+    }
+}
+
+impl<const DIM: usize> Serialize for PublicKey<DIM> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize(Self::BYTES, |w| self.to_bytes(w), serializer)
+    }
+}
+
+impl<'de, const DIM: usize> Deserialize<'de> for PublicKey<DIM> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize(Self::BYTES, Self::try_from_bytes, deserializer)
+    }
+}
+
+impl<const DIM: usize> Serialize for CipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize(Self::BYTES, |w| self.to_bytes(w), serializer)
+    }
+}
+
+impl<'de, const DIM: usize> Deserialize<'de> for CipherText<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize(Self::BYTES, Self::try_from_bytes, deserializer)
+    }
+}
+
+// the secret key alone, without the public key, it is the secret polynomial vector
+// followed by the implicit rejection value, `384 * DIM + 32` bytes
+#[cfg(feature = "serde-secret")]
+impl<const DIM: usize> Serialize for SecretKey<DIM> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let write = |w: &mut ArrayWriter<'_>| {
+            self.inner.to_bytes(w);
+            w.update(&self.reject);
+        };
+        serialize(384 * DIM + 32, write, serializer)
+    }
+}
+
+#[cfg(feature = "serde-secret")]
+impl<'de, const DIM: usize> Deserialize<'de> for SecretKey<DIM> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let read = |b: &[u8]| {
+            let mut reject = [0; 32];
+            reject.clone_from_slice(&b[(384 * DIM)..]);
+            let inner = indcpa::SecretKey::try_from_bytes(&b[..(384 * DIM)])?;
+            Ok(SecretKey { inner, reject })
+        };
+        deserialize(384 * DIM + 32, read, deserializer)
+    }
+}
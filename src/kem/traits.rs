@@ -0,0 +1,52 @@
+//! Implementation of the traits from `kem` crate.
+
+use core::convert::Infallible;
+
+use kem::{Encapsulate, Decapsulate};
+use rand_core::CryptoRngCore;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use super::{
+    super::config::{Dim, Config},
+    PublicKey, CipherText, KeyPair, encapsulate,
+};
+
+/// The shared secret.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct SharedSecret(pub [u8; 32]);
+
+impl AsRef<[u8]> for SharedSecret {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const DIM: usize> Encapsulate<CipherText<DIM>, SharedSecret> for PublicKey<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    type Error = Infallible;
+
+    fn encapsulate(
+        &self,
+        rng: &mut impl CryptoRngCore,
+    ) -> Result<(CipherText<DIM>, SharedSecret), Self::Error> {
+        let mut seed = [0; 32];
+        rng.fill_bytes(&mut seed);
+        let (ct, ss) = encapsulate(seed, self);
+        seed.zeroize();
+        Ok((ct, SharedSecret(ss)))
+    }
+}
+
+// the secret key alone is not enough, decapsulation needs the public key as well
+impl<const DIM: usize> Decapsulate<CipherText<DIM>, SharedSecret> for KeyPair<DIM>
+where
+    Dim<DIM>: Config<32>,
+{
+    type Error = Infallible;
+
+    fn decapsulate(&self, encapsulated_key: &CipherText<DIM>) -> Result<SharedSecret, Self::Error> {
+        Ok(SharedSecret(KeyPair::decapsulate(self, encapsulated_key)))
+    }
+}
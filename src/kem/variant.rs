@@ -0,0 +1,321 @@
+//! The parameter set selected at runtime, for protocols which negotiate it.
+
+use rand::{RngCore, CryptoRng};
+use sha3::digest::Update;
+
+use super::{
+    KeySeed, SecretKey, PublicKey, CipherText, DecodeError, key_pair, generate, encapsulate,
+    decapsulate, try_load_key_pair,
+};
+
+/// The parameter set, known only at runtime. The keys and cipher texts are wrapped
+/// in enums, the functions dispatch to the corresponding `Kyber<DIM>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Variant {
+    Kyber512,
+    Kyber768,
+    Kyber1024,
+}
+
+// the crate does not allocate, the enums are as large as the largest variant
+/// The secret key of any parameter set.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AnySecretKey {
+    Kyber512(SecretKey<2>),
+    Kyber768(SecretKey<3>),
+    Kyber1024(SecretKey<4>),
+}
+
+/// The public key of any parameter set.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AnyPublicKey {
+    Kyber512(PublicKey<2>),
+    Kyber768(PublicKey<3>),
+    Kyber1024(PublicKey<4>),
+}
+
+/// The cipher text of any parameter set.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+pub enum AnyCipherText {
+    Kyber512(CipherText<2>),
+    Kyber768(CipherText<3>),
+    Kyber1024(CipherText<4>),
+}
+
+impl Variant {
+    /// The parameter set with the given dimension of the module, `2`, `3` or `4`.
+    #[must_use]
+    pub const fn from_dim(dim: usize) -> Option<Self> {
+        match dim {
+            2 => Some(Variant::Kyber512),
+            3 => Some(Variant::Kyber768),
+            4 => Some(Variant::Kyber1024),
+            _ => None,
+        }
+    }
+
+    /// The dimension of the module.
+    #[must_use]
+    pub const fn dim(self) -> usize {
+        match self {
+            Variant::Kyber512 => 2,
+            Variant::Kyber768 => 3,
+            Variant::Kyber1024 => 4,
+        }
+    }
+
+    /// Length of the serialized public key.
+    #[must_use]
+    pub const fn public_key_bytes(self) -> usize {
+        match self {
+            Variant::Kyber512 => PublicKey::<2>::BYTES,
+            Variant::Kyber768 => PublicKey::<3>::BYTES,
+            Variant::Kyber1024 => PublicKey::<4>::BYTES,
+        }
+    }
+
+    /// Length of the serialized key pair.
+    #[must_use]
+    pub const fn secret_key_bytes(self) -> usize {
+        match self {
+            Variant::Kyber512 => SecretKey::<2>::BYTES,
+            Variant::Kyber768 => SecretKey::<3>::BYTES,
+            Variant::Kyber1024 => SecretKey::<4>::BYTES,
+        }
+    }
+
+    /// Length of the serialized cipher text.
+    #[must_use]
+    pub const fn cipher_text_bytes(self) -> usize {
+        match self {
+            Variant::Kyber512 => CipherText::<2>::BYTES,
+            Variant::Kyber768 => CipherText::<3>::BYTES,
+            Variant::Kyber1024 => CipherText::<4>::BYTES,
+        }
+    }
+
+    /// Creates a key pair from the seed.
+    #[must_use]
+    pub fn key_pair(self, s: KeySeed) -> (AnySecretKey, AnyPublicKey) {
+        match self {
+            Variant::Kyber512 => {
+                let (sk, pk) = key_pair(s);
+                (AnySecretKey::Kyber512(sk), AnyPublicKey::Kyber512(pk))
+            }
+            Variant::Kyber768 => {
+                let (sk, pk) = key_pair(s);
+                (AnySecretKey::Kyber768(sk), AnyPublicKey::Kyber768(pk))
+            }
+            Variant::Kyber1024 => {
+                let (sk, pk) = key_pair(s);
+                (AnySecretKey::Kyber1024(sk), AnyPublicKey::Kyber1024(pk))
+            }
+        }
+    }
+
+    /// Creates a key pair from the seed sampled from the random number generator.
+    #[must_use]
+    pub fn generate<R>(self, rng: &mut R) -> (AnySecretKey, AnyPublicKey)
+    where
+        R: RngCore + CryptoRng,
+    {
+        match self {
+            Variant::Kyber512 => {
+                let (sk, pk) = generate(rng);
+                (AnySecretKey::Kyber512(sk), AnyPublicKey::Kyber512(pk))
+            }
+            Variant::Kyber768 => {
+                let (sk, pk) = generate(rng);
+                (AnySecretKey::Kyber768(sk), AnyPublicKey::Kyber768(pk))
+            }
+            Variant::Kyber1024 => {
+                let (sk, pk) = generate(rng);
+                (AnySecretKey::Kyber1024(sk), AnyPublicKey::Kyber1024(pk))
+            }
+        }
+    }
+
+    /// Encapsulates the secret using public key of receiver.
+    /// Returns `None` if the key belongs to another parameter set.
+    #[must_use]
+    pub fn encapsulate(
+        self,
+        seed: [u8; 32],
+        public_key: &AnyPublicKey,
+    ) -> Option<(AnyCipherText, [u8; 32])> {
+        match (self, public_key) {
+            (Variant::Kyber512, AnyPublicKey::Kyber512(pk)) => {
+                let (ct, ss) = encapsulate(seed, pk);
+                Some((AnyCipherText::Kyber512(ct), ss))
+            }
+            (Variant::Kyber768, AnyPublicKey::Kyber768(pk)) => {
+                let (ct, ss) = encapsulate(seed, pk);
+                Some((AnyCipherText::Kyber768(ct), ss))
+            }
+            (Variant::Kyber1024, AnyPublicKey::Kyber1024(pk)) => {
+                let (ct, ss) = encapsulate(seed, pk);
+                Some((AnyCipherText::Kyber1024(ct), ss))
+            }
+            _ => None,
+        }
+    }
+
+    /// Decapsulate the secret from cipher text using secret key.
+    /// Returns `None` if the keys or the cipher text belong to another parameter set.
+    #[must_use]
+    pub fn decapsulate(
+        self,
+        secret_key: &AnySecretKey,
+        public_key: &AnyPublicKey,
+        cipher_text: &AnyCipherText,
+    ) -> Option<[u8; 32]> {
+        use self::{AnyCipherText as C, AnyPublicKey as P, AnySecretKey as S};
+
+        match (self, secret_key, public_key, cipher_text) {
+            (Variant::Kyber512, S::Kyber512(sk), P::Kyber512(pk), C::Kyber512(ct)) => {
+                Some(decapsulate(sk, pk, ct))
+            }
+            (Variant::Kyber768, S::Kyber768(sk), P::Kyber768(pk), C::Kyber768(ct)) => {
+                Some(decapsulate(sk, pk, ct))
+            }
+            (Variant::Kyber1024, S::Kyber1024(sk), P::Kyber1024(pk), C::Kyber1024(ct)) => {
+                Some(decapsulate(sk, pk, ct))
+            }
+            _ => None,
+        }
+    }
+
+    /// Deserialize a key pair from bytes
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `secret_key_bytes`
+    /// or if some coefficient is not canonical,
+    /// or if the stored hash of the public key is not its hash
+    pub fn try_load_key_pair(self, b: &[u8]) -> Result<(AnySecretKey, AnyPublicKey), DecodeError> {
+        Ok(match self {
+            Variant::Kyber512 => {
+                let (sk, pk) = try_load_key_pair(b)?;
+                (AnySecretKey::Kyber512(sk), AnyPublicKey::Kyber512(pk))
+            }
+            Variant::Kyber768 => {
+                let (sk, pk) = try_load_key_pair(b)?;
+                (AnySecretKey::Kyber768(sk), AnyPublicKey::Kyber768(pk))
+            }
+            Variant::Kyber1024 => {
+                let (sk, pk) = try_load_key_pair(b)?;
+                (AnySecretKey::Kyber1024(sk), AnyPublicKey::Kyber1024(pk))
+            }
+        })
+    }
+
+    /// Deserialize the public key
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `public_key_bytes`
+    /// or if some coefficient is not canonical
+    pub fn try_public_key_from_bytes(self, b: &[u8]) -> Result<AnyPublicKey, DecodeError> {
+        Ok(match self {
+            Variant::Kyber512 => AnyPublicKey::Kyber512(PublicKey::try_from_bytes(b)?),
+            Variant::Kyber768 => AnyPublicKey::Kyber768(PublicKey::try_from_bytes(b)?),
+            Variant::Kyber1024 => AnyPublicKey::Kyber1024(PublicKey::try_from_bytes(b)?),
+        })
+    }
+
+    /// Deserialize the cipher text
+    ///
+    /// # Errors
+    ///
+    /// will return an error if length of bytes not equal to `cipher_text_bytes`
+    pub fn try_cipher_text_from_bytes(self, b: &[u8]) -> Result<AnyCipherText, DecodeError> {
+        Ok(match self {
+            Variant::Kyber512 => AnyCipherText::Kyber512(CipherText::try_from_bytes(b)?),
+            Variant::Kyber768 => AnyCipherText::Kyber768(CipherText::try_from_bytes(b)?),
+            Variant::Kyber1024 => AnyCipherText::Kyber1024(CipherText::try_from_bytes(b)?),
+        })
+    }
+}
+
+impl AnySecretKey {
+    #[must_use]
+    pub const fn variant(&self) -> Variant {
+        match self {
+            AnySecretKey::Kyber512(_) => Variant::Kyber512,
+            AnySecretKey::Kyber768(_) => Variant::Kyber768,
+            AnySecretKey::Kyber1024(_) => Variant::Kyber1024,
+        }
+    }
+
+    /// Serialize the key pair in the format `Variant::try_load_key_pair` expects.
+    /// Returns `None` and writes nothing if the public key belongs to another parameter set.
+    #[must_use]
+    pub fn to_bytes<U>(&self, public_key: &AnyPublicKey, buffer: &mut U) -> Option<()>
+    where
+        U: Update,
+    {
+        match (self, public_key) {
+            (AnySecretKey::Kyber512(sk), AnyPublicKey::Kyber512(pk)) => sk.to_bytes(pk, buffer),
+            (AnySecretKey::Kyber768(sk), AnyPublicKey::Kyber768(pk)) => sk.to_bytes(pk, buffer),
+            (AnySecretKey::Kyber1024(sk), AnyPublicKey::Kyber1024(pk)) => sk.to_bytes(pk, buffer),
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+impl AnyPublicKey {
+    #[must_use]
+    pub const fn variant(&self) -> Variant {
+        match self {
+            AnyPublicKey::Kyber512(_) => Variant::Kyber512,
+            AnyPublicKey::Kyber768(_) => Variant::Kyber768,
+            AnyPublicKey::Kyber1024(_) => Variant::Kyber1024,
+        }
+    }
+
+    #[must_use]
+    pub const fn hash(&self) -> [u8; 32] {
+        match self {
+            AnyPublicKey::Kyber512(pk) => pk.hash(),
+            AnyPublicKey::Kyber768(pk) => pk.hash(),
+            AnyPublicKey::Kyber1024(pk) => pk.hash(),
+        }
+    }
+
+    pub fn to_bytes<U>(&self, buffer: &mut U)
+    where
+        U: Update,
+    {
+        match self {
+            AnyPublicKey::Kyber512(pk) => pk.to_bytes(buffer),
+            AnyPublicKey::Kyber768(pk) => pk.to_bytes(buffer),
+            AnyPublicKey::Kyber1024(pk) => pk.to_bytes(buffer),
+        }
+    }
+}
+
+impl AnyCipherText {
+    #[must_use]
+    pub const fn variant(&self) -> Variant {
+        match self {
+            AnyCipherText::Kyber512(_) => Variant::Kyber512,
+            AnyCipherText::Kyber768(_) => Variant::Kyber768,
+            AnyCipherText::Kyber1024(_) => Variant::Kyber1024,
+        }
+    }
+
+    pub fn to_bytes<U>(&self, buffer: &mut U)
+    where
+        U: Update,
+    {
+        match self {
+            AnyCipherText::Kyber512(ct) => ct.to_bytes(buffer),
+            AnyCipherText::Kyber768(ct) => ct.to_bytes(buffer),
+            AnyCipherText::Kyber1024(ct) => ct.to_bytes(buffer),
+        }
+    }
+}
@@ -14,18 +14,27 @@
 #![allow(clippy::use_self)]
 #![no_std]
 
-#[cfg(test)]
-#[macro_use]
+#[cfg(any(test, feature = "std"))]
+#[cfg_attr(test, macro_use)]
 extern crate std;
 
+mod error;
 mod array;
 mod coefficient;
 mod block;
 mod poly;
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod avx2;
 mod generator;
+mod symmetric;
 pub mod config;
 mod indcpa;
 pub mod kem;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
 
 #[cfg(test)]
 mod tests;
@@ -1,10 +1,49 @@
-use core::ops::{Index, IndexMut, AddAssign, SubAssign};
+use core::{
+    iter, slice,
+    ops::{Index, IndexMut, Add, Sub, AddAssign, SubAssign, Mul, MulAssign},
+};
 
-use sha3::digest::{Update, ExtendableOutput, XofReader};
+use sha3::digest::{Update, XofReader};
+use subtle::{Choice, ConstantTimeEq, ConditionallySelectable};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-use super::{array::Array, coefficient::Coefficient, block::PolyBlock, generator::Buf};
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+use super::avx2;
+use super::{
+    array::Array,
+    coefficient::Coefficient,
+    block::PolyBlock,
+    generator::Buf,
+    error::{DecodeError, check_length},
+    symmetric::Symmetric,
+};
 
+/// The lazy `Poly::compress`, the same type for each number of bits,
+/// so `Config` can select it by its constants.
+pub type CompressIter<'a> = iter::FlatMap<
+    slice::Iter<'a, PolyBlock>,
+    iter::Take<core::array::IntoIter<u8, 11>>,
+    fn(&PolyBlock) -> iter::Take<core::array::IntoIter<u8, 11>>,
+>;
+
+fn compress_block<const X: u32>(a: &PolyBlock) -> iter::Take<core::array::IntoIter<u8, 11>> {
+    let mut b = [0; 11];
+    match X {
+        1 => b[0] = a.compress_1(),
+        3 => b[..3].clone_from_slice(&a.compress_3()),
+        4 => b[..4].clone_from_slice(&a.compress_4()),
+        5 => b[..5].clone_from_slice(&a.compress_5()),
+        10 => b[..10].clone_from_slice(&a.compress_10()),
+        11 => b.clone_from_slice(&a.compress_11()),
+        _ => unreachable!("unsupported number of bits {X}"),
+    }
+    b.into_iter().take(X as usize)
+}
+
+// all the types down to `i16` are `repr(transparent)`,
+// so the polynomial is `8 * SIZE` consecutive integers
 #[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
 pub struct Poly<const SIZE: usize, const B: bool>(Array<PolyBlock, SIZE>);
 
 impl<const SIZE: usize, const B: bool> Index<usize> for Poly<SIZE, B> {
@@ -26,7 +65,7 @@ impl<const SIZE: usize, const B: bool> IndexMut<usize> for Poly<SIZE, B> {
 impl<'a, const SIZE: usize, const B: bool> AddAssign<&'a Self> for Poly<SIZE, B> {
     fn add_assign(&mut self, rhs: &'a Self) {
         for i in 0..(SIZE * 8) {
-            self[i] = self[i] + rhs[i];
+            self[i] += rhs[i];
         }
     }
 }
@@ -34,11 +73,86 @@ impl<'a, const SIZE: usize, const B: bool> AddAssign<&'a Self> for Poly<SIZE, B>
 impl<'a, const SIZE: usize, const B: bool> SubAssign<&'a Self> for Poly<SIZE, B> {
     fn sub_assign(&mut self, rhs: &'a Self) {
         for i in 0..(SIZE * 8) {
-            self[i] = self[i] - rhs[i];
+            self[i] -= rhs[i];
+        }
+    }
+}
+
+impl<const SIZE: usize, const B: bool> Add for &Poly<SIZE, B> {
+    type Output = Poly<SIZE, B>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut r = *self;
+        r += rhs;
+        r
+    }
+}
+
+impl<const SIZE: usize, const B: bool> Sub for &Poly<SIZE, B> {
+    type Output = Poly<SIZE, B>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let mut r = *self;
+        r -= rhs;
+        r
+    }
+}
+
+// the Montgomery multiplication, the result is scaled by `2^-16`
+impl<const SIZE: usize, const B: bool> MulAssign<Coefficient> for Poly<SIZE, B> {
+    fn mul_assign(&mut self, rhs: Coefficient) {
+        for a in self.coefficients_mut() {
+            *a = (Coefficient(*a) * rhs).0;
         }
     }
 }
 
+impl<const SIZE: usize, const B: bool> Mul<Coefficient> for Poly<SIZE, B> {
+    type Output = Self;
+
+    fn mul(mut self, rhs: Coefficient) -> Self::Output {
+        self *= rhs;
+        self
+    }
+}
+
+// the pointwise product in the NTT domain, the same as `PolyMul::mul_montgomery`,
+// so the result is scaled by `2^-16` and not reduced
+impl<const SIZE: usize> Mul for &Poly<SIZE, false> {
+    type Output = Poly<SIZE, false>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.mul_montgomery(rhs)
+    }
+}
+
+impl<'a, const SIZE: usize> MulAssign<&'a Self> for Poly<SIZE, false> {
+    fn mul_assign(&mut self, rhs: &'a Self) {
+        *self = &*self * rhs;
+    }
+}
+
+// compares the serialized representation
+impl<const SIZE: usize, const B: bool> ConstantTimeEq for Poly<SIZE, B> {
+    #[inline]
+    fn ct_eq(&self, other: &Self) -> Choice {
+        (0..(SIZE * 8)).fold(Choice::from(1), |x, i| {
+            x & self[i].pack().ct_eq(&other[i].pack())
+        })
+    }
+}
+
+// selects all the coefficients, no branch on the choice, e.g. for the implicit rejection
+impl<const SIZE: usize, const B: bool> ConditionallySelectable for Poly<SIZE, B> {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Poly(
+            (0..SIZE)
+                .map(|i| PolyBlock::conditional_select(&a.0[i], &b.0[i], choice))
+                .collect(),
+        )
+    }
+}
+
 pub trait PolyMul {
     fn mul_montgomery(&self, rhs: &Self) -> Self;
 
@@ -56,14 +170,33 @@ impl<const SIZE: usize> AsRef<Self> for Poly<SIZE, false> {
     }
 }
 
-impl PolyMul for Poly<32, false> {
-    #[must_use]
+impl<const SIZE: usize, const B: bool> Poly<SIZE, B> {
+    // The ring of degree `n = 8 * SIZE` uses the first `n / 2` twiddles of the table,
+    // since `17 ^ (256 / n)` is its root of unity. The last layer starts at `n / 4`.
+    const DEGREE: usize = {
+        assert!(
+            SIZE.is_power_of_two() && SIZE <= 32,
+            "the degree must be a power of two not greater than 256",
+        );
+        8 * SIZE
+    };
+
+    const ZETA_OFFSET: usize = Self::DEGREE / 4;
+
+    // The inverse transform of `log2(n) - 1` layers scales by `n / 2`,
+    // so the factor is `2^32 / (n / 2)`, the same as `Coefficient::F` scaled by `256 / n`.
+    const F: Coefficient = Coefficient(
+        ((Coefficient::F.0 as i32 * (256 / Self::DEGREE) as i32) % Coefficient::Q as i32) as i16,
+    );
+}
+
+impl<const SIZE: usize> PolyMul for Poly<SIZE, false> {
     fn mul_montgomery(&self, rhs: &Self) -> Self {
-        let array = (0..32)
+        let array = (0..SIZE)
             .map(|i| {
                 let zetas = [
-                    Coefficient(ZETAS[64 + 2 * i]),
-                    Coefficient(ZETAS[64 + 2 * i + 1]),
+                    Coefficient(ZETAS[Self::ZETA_OFFSET + 2 * i]),
+                    Coefficient(ZETAS[Self::ZETA_OFFSET + 2 * i + 1]),
                 ];
                 self.0[i].mul(&rhs.0[i], zetas)
             })
@@ -72,19 +205,15 @@ impl PolyMul for Poly<32, false> {
         Poly(array)
     }
 
-    #[must_use]
-    fn mul_fold_montgomery<'a, 'b, A, B, Br>(mut a: A, mut b: B) -> Self
+    fn mul_fold_montgomery<'a, 'b, A, B, Br>(a: A, b: B) -> Self
     where
         Self: 'a + 'b,
         A: Iterator<Item = &'a Self>,
         B: Iterator<Item = Br>,
         Br: AsRef<Self>,
     {
-        let af = a.next().expect("not empty iterator");
-        let bf = b.next().expect("not empty iterator");
-        let init = af.mul_montgomery(bf.as_ref());
-        let p = a.zip(b).fold(init, |mut r, (a, b)| {
-            r += &a.mul_montgomery(b.as_ref());
+        let p = a.zip(b).fold(Self::zero(), |mut r, (a, b)| {
+            r += &(a * b.as_ref());
             r
         });
         p.barrett_reduce()
@@ -93,59 +222,184 @@ impl PolyMul for Poly<32, false> {
 
 impl<const SIZE: usize, const B: bool> Poly<SIZE, B> {
     #[must_use]
-    pub fn from_bytes(bytes: &[u8]) -> Self {
-        Poly(bytes.chunks(12).map(PolyBlock::from_bytes).collect())
+    pub fn zero() -> Self {
+        let mut it = iter::repeat(Coefficient(0));
+        Poly((0..SIZE).map(|_| PolyBlock::new(&mut it)).collect())
+    }
+
+    /// Compares the serialized representation, so any multiple of `q` is zero.
+    pub fn ct_is_zero(&self) -> Choice {
+        (0..(SIZE * 8)).fold(Choice::from(1), |x, i| x & self[i].pack().ct_eq(&0))
+    }
+
+    // the `12 * SIZE` bytes in blocks, the length is checked before the blocks are sliced
+    fn blocks(bytes: &[u8]) -> Result<impl Iterator<Item = &[u8; 12]>, DecodeError> {
+        check_length(bytes, 12 * SIZE)?;
+        Ok(bytes
+            .chunks_exact(12)
+            .map(|b| b.try_into().unwrap_or_else(|_| unreachable!())))
+    }
+
+    /// Takes the lower 12 bits of each coefficient, fails on a wrong length only.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Ok(Poly(
+            Self::blocks(bytes)?.map(PolyBlock::from_bytes).collect(),
+        ))
+    }
+
+    /// Rejects coefficients not less than `q`.
+    pub fn from_bytes_checked(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Array::try_new(&mut Self::blocks(bytes)?.map(PolyBlock::from_bytes_checked)).map(Poly)
+    }
+
+    /// The check of `from_bytes_checked` without decoding the polynomial.
+    pub fn check_bytes(bytes: &[u8]) -> Result<(), DecodeError> {
+        Self::blocks(bytes)?.try_for_each(PolyBlock::check_bytes)
     }
 
     pub fn to_bytes<U>(self, update: &mut U)
     where
         U: Update,
     {
-        for a in self.0.as_ref() {
+        for a in &self.0 {
             update.update(&a.to_bytes());
         }
     }
 
+    /// The bytes `to_bytes` gives, block by block.
+    pub fn byte_iter(&self) -> impl Iterator<Item = u8> + '_ {
+        self.0.iter().flat_map(|a| a.to_bytes())
+    }
+
     #[must_use]
     pub fn barrett_reduce(mut self) -> Self {
-        for i in 0..(SIZE * 8) {
-            self[i] = Coefficient::barrett_reduce(self[i].0);
-        }
+        self.barrett_reduce_in_place();
         self
     }
+
+    // works on the plain integers rather than through `Index`, so the loop is vectorized
+    #[inline]
+    fn barrett_reduce_in_place(&mut self) {
+        for a in self.coefficients_mut() {
+            *a = Coefficient::barrett_reduce(*a).0;
+        }
+    }
+
+    #[inline]
+    fn coefficients_mut(&mut self) -> &mut [i16] {
+        unsafe { slice::from_raw_parts_mut((self as *mut Self).cast::<i16>(), SIZE * 8) }
+    }
 }
 
 impl<const SIZE: usize> Poly<SIZE, false> {
-    pub fn get_uniform<D>(seed: &[u8; 32], i: usize, j: usize) -> Self
+    /// The coefficient `i` in the natural order of the NTT domain, for debugging.
+    ///
+    /// The pair `2 j, 2 j + 1` is the remainder of the polynomial modulo `X^2 - r`, where
+    /// `r` is `zeta^(2 brv(j) + 1)` as the transform leaves it, the layout of the reference
+    /// implementation, and `zeta^(2 j + 1)` in the natural order. Here `zeta` is the root
+    /// of unity of order `n`, `17` for the degree 256, `brv` reverses `log2(n / 2)` bits.
+    /// The layout is the same with the `simd` feature.
+    #[must_use]
+    pub fn coefficient_natural(&self, i: usize) -> Coefficient {
+        let bits = (Self::DEGREE / 2).trailing_zeros();
+        let pair = (i / 2).reverse_bits() >> (usize::BITS - bits);
+        self[2 * pair + i % 2]
+    }
+
+    /// The coefficients in the natural order, see `coefficient_natural`.
+    pub fn iter_natural(&self) -> impl Iterator<Item = Coefficient> + '_ {
+        (0..Self::DEGREE).map(move |i| self.coefficient_natural(i))
+    }
+
+    /// The pointwise product, `&a * &b`, reduced to the representatives
+    /// `barrett_reduce` gives.
+    #[must_use]
+    pub fn mul_reduce(&self, rhs: &Self) -> Self {
+        (self * rhs).barrett_reduce()
+    }
+
+    pub fn get_uniform<S>(seed: &[u8; 32], i: usize, j: usize) -> Self
+    where
+        S: Symmetric,
+    {
+        Self::uniform(S::xof(seed, i as u8, j as u8))
+    }
+
+    /// Rejection sampling from any stream, the domain separation is up to the caller.
+    /// The stream must fill each buffer, see `noise`.
+    pub fn uniform<R>(xof: R) -> Self
     where
-        D: Default + Update + ExtendableOutput,
+        R: XofReader,
     {
-        let mut it = Buf::new::<D>(seed, i, j)
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if avx2::available() {
+            return unsafe { Self::uniform_avx2(xof) };
+        }
+
+        Self::uniform_scalar(xof)
+    }
+
+    fn uniform_scalar<R>(xof: R) -> Self
+    where
+        R: XofReader,
+    {
+        let mut it = Buf::new(xof)
             .filter(|x| x.lt(&Coefficient::Q))
             .map(Coefficient);
         Poly((0..SIZE).map(|_| PolyBlock::new(&mut it)).collect())
     }
 
+    // the vectorized loop takes as much as it can of the first three blocks,
+    // the rest of them and the rest of the stream go through `Buf`, as in `uniform_scalar`
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    unsafe fn uniform_avx2<R>(mut xof: R) -> Self
+    where
+        R: XofReader,
+    {
+        let mut buf = [0; avx2::REJ_BYTES + 8];
+        xof.read(&mut buf[..avx2::REJ_BYTES]);
+
+        let mut p = Self::zero();
+        let out = p.coefficients_mut();
+        let (ctr, pos) = avx2::rej_uniform(out, &buf);
+
+        let tail = buf[pos..avx2::REJ_BYTES].chunks(3).flat_map(|b| {
+            let d1 = (u16::from(b[0]) | u16::from(b[1]) << 8) & 0xfff;
+            let d2 = (u16::from(b[1] >> 4) | u16::from(b[2]) << 4) & 0xfff;
+            [d1 as i16, d2 as i16]
+        });
+        let it = tail.chain(Buf::new(xof)).filter(|x| x.lt(&Coefficient::Q));
+        for (c, x) in out[ctr..].iter_mut().zip(it) {
+            *c = x;
+        }
+
+        p
+    }
+
     #[must_use]
-    pub fn montgomery_reduce(mut self) -> Self {
+    pub fn montgomery_reduce(self) -> Self {
         let f = ((1u64 << 32) % Coefficient::Q as u64) as i16;
-        for i in 0..(SIZE * 8) {
-            self[i] = self[i] * Coefficient(f);
-        }
-        self
+        self * Coefficient(f)
     }
 }
 
 impl<const SIZE: usize> Poly<SIZE, true> {
-    pub fn get_noise<D, const I: usize>(seed: &[u8; 32], nonce: usize) -> Self
+    /// The noise from `PRF(seed, nonce)`, the nonce is a single byte as the specification has it.
+    pub fn get_noise<S, const I: usize>(seed: &[u8; 32], nonce: u8) -> Self
     where
-        D: Default + Update + ExtendableOutput,
+        S: Symmetric,
     {
-        let mut reader = D::default()
-            .chain(seed)
-            .chain([nonce as u8].as_ref())
-            .finalize_xof();
+        Self::noise::<_, I>(S::prf(seed, nonce))
+    }
 
+    /// The centered binomial distribution from any stream, `I` bytes per block of 8 coefficients.
+    ///
+    /// `XofReader::read` returns nothing, it must fill the whole buffer, a short read cannot
+    /// be detected here, the untouched bytes stay zero. SHAKE and the AES-256 key stream do.
+    pub fn noise<R, const I: usize>(mut reader: R) -> Self
+    where
+        R: XofReader,
+    {
         let array = (0..SIZE)
             .map(|_| {
                 let mut b = [0; I];
@@ -161,8 +415,10 @@ impl<const SIZE: usize> Poly<SIZE, true> {
     where
         U: Update,
     {
-        for a in self.0.as_ref() {
+        for a in &self.0 {
             match X {
+                1 => update.update(&[a.compress_1()]),
+                3 => update.update(&a.compress_3()),
                 4 => update.update(&a.compress_4()),
                 5 => update.update(&a.compress_5()),
                 10 => update.update(&a.compress_10()),
@@ -172,6 +428,11 @@ impl<const SIZE: usize> Poly<SIZE, true> {
         }
     }
 
+    /// The bytes `compress` gives, `X` bits per coefficient, so `X` bytes per block.
+    pub fn compress_iter<const X: u32>(&self) -> CompressIter<'_> {
+        self.0.iter().flat_map(compress_block::<X>)
+    }
+
     pub fn decompress<const X: u32>(bytes: &[u8]) -> Self {
         Poly(
             bytes
@@ -181,88 +442,175 @@ impl<const SIZE: usize> Poly<SIZE, true> {
         )
     }
 
-    pub fn from_msg(msg: &[u8; SIZE]) -> Self {
-        Poly(msg.iter().copied().map(PolyBlock::decompress_1).collect())
+    // a bit of the message per coefficient, a byte per block
+    const MESSAGE_BLOCKS: usize = {
+        assert!(
+            SIZE == 32,
+            "the message is 256 bits, the polynomial must be of degree 256",
+        );
+        SIZE
+    };
+
+    pub fn from_msg(msg: &Message) -> Self {
+        Poly(
+            msg.0
+                .iter()
+                .take(Self::MESSAGE_BLOCKS)
+                .copied()
+                .map(PolyBlock::decompress_1)
+                .collect(),
+        )
     }
 
-    pub fn to_msg(self) -> [u8; SIZE] {
-        let mut b = [0; SIZE];
-        for (a, b) in self.0.as_ref().iter().zip(b.iter_mut()) {
+    pub fn to_msg(self) -> Message {
+        let mut m = Message([0; 32]);
+        for (a, b) in self.0.iter().zip(m.0.iter_mut()).take(Self::MESSAGE_BLOCKS) {
             *b = a.compress_1();
         }
-        b
+        m
+    }
+
+    /// The message and the smallest distance of a coefficient to the decision boundary
+    /// of `compress_1`, by how much it may move before its bit flips. Not constant time.
+    #[cfg(feature = "testing")]
+    pub fn to_msg_with_margin(self) -> (Message, u16) {
+        let q = Coefficient::Q as u16;
+        // `compress_1` gives one for `833..=2496`
+        let (low, high) = ((q + 3) / 4, (3 * q - 3) / 4);
+        let margin = (0..(Self::MESSAGE_BLOCKS * 8))
+            .map(|i| {
+                let x = self[i].pack();
+                if x < low {
+                    low - x
+                } else if x > high {
+                    x - high
+                } else {
+                    (x + 1 - low).min(high + 1 - x)
+                }
+            })
+            .min()
+            .unwrap_or_default();
+        (self.to_msg(), margin)
     }
 }
 
+/// The message of the public key encryption, exactly 256 bits, whatever the ring is.
+/// Wipes itself on drop.
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct Message(pub [u8; 32]);
+
 pub trait Ntt {
     type Output: Ntt;
 
     fn ntt(self) -> Self::Output;
 }
 
-const ZETAS: [i16; 128] = [
-    -1044, -758, -359, -1517, 1493, 1422, 287, 202, -171, 622, 1577, 182, 962, -1202, -1474, 1468,
-    573, -1325, 264, 383, -829, 1458, -1602, -130, -681, 1017, 732, 608, -1542, 411, -205, -1571,
-    1223, 652, -552, 1015, -1293, 1491, -282, -1544, 516, -8, -320, -666, -1618, -1162, 126, 1469,
-    -853, -90, -271, 830, 107, -1421, -247, -951, -398, 961, -1508, -725, 448, -1065, 677, -1275,
-    -1103, 430, 555, 843, -1251, 871, 1550, 105, 422, 587, 177, -235, -291, -460, 1574, 1653, -246,
-    778, 1159, -147, -777, 1483, -602, 1119, -1590, 644, -872, 349, 418, 329, -156, -75, 817, 1097,
-    603, 610, 1322, -1285, -1465, 384, -1215, -136, 1218, -1335, -874, 220, -1187, -1659, -1185,
-    -1530, -1278, 794, -1510, -854, -870, 478, -108, -308, 996, 991, 958, -1460, 1522, 1628,
-];
+pub const ZETAS: [i16; 128] = {
+    let mut zetas = [0; 128];
+    let mut i = 0;
+    while i < 128 {
+        zetas[i] = Coefficient::zeta(i, 7).0;
+        i += 1;
+    }
+    zetas
+};
 
-impl Ntt for Poly<32, true> {
-    type Output = Poly<32, false>;
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+impl<const SIZE: usize, const B: bool> Poly<SIZE, B> {
+    #[inline]
+    fn as_array_mut(&mut self) -> &mut [i16; 256] {
+        self.coefficients_mut()
+            .try_into()
+            .expect("polynomial of 256 coefficients")
+    }
+}
 
-    #[must_use]
-    fn ntt(self) -> Self::Output {
-        let mut r = Poly(self.0);
+impl<const SIZE: usize> Ntt for Poly<SIZE, true> {
+    type Output = Poly<SIZE, false>;
+
+    fn ntt(mut self) -> Self::Output {
+        self.ntt_in_place();
+        Poly(self.0)
+    }
+}
+
+impl<const SIZE: usize> Ntt for Poly<SIZE, false> {
+    type Output = Poly<SIZE, true>;
+
+    fn ntt(mut self) -> Self::Output {
+        self.inverse_ntt_in_place();
+        Poly(self.0)
+    }
+}
+
+// the transforms do not change the domain flag, it is up to `Ntt::ntt` to relabel the result,
+// so they are private to this module
+impl<const SIZE: usize, const B: bool> Poly<SIZE, B> {
+    #[inline]
+    fn ntt_in_place(&mut self) {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if SIZE == 32 && avx2::available() {
+            unsafe { avx2::ntt(self.as_array_mut()) };
+            return;
+        }
+
+        self.ntt_in_place_scalar();
+    }
+
+    #[inline]
+    fn inverse_ntt_in_place(&mut self) {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if SIZE == 32 && avx2::available() {
+            unsafe { avx2::inverse_ntt(self.as_array_mut()) };
+            return;
+        }
+
+        self.inverse_ntt_in_place_scalar();
+    }
+
+    fn ntt_in_place_scalar(&mut self) {
+        let r = self;
 
         let mut j;
         let mut k = 1usize;
-        let mut len = 128;
+        let mut len = Self::DEGREE / 2;
 
         while len >= 2 {
             let mut start = 0;
-            while start < 256 {
+            while start < Self::DEGREE {
                 let zeta = Coefficient(ZETAS[k]);
                 k += 1;
                 j = start;
                 while j < (start + len) {
                     let t = zeta * r[j + len];
                     r[j + len] = r[j] - t;
-                    r[j] = r[j] + t;
+                    r[j] += t;
                     j += 1;
                 }
                 start = j + len;
             }
             len >>= 1;
         }
-        r.barrett_reduce()
+        r.barrett_reduce_in_place();
     }
-}
 
-impl Ntt for Poly<32, false> {
-    type Output = Poly<32, true>;
-
-    #[must_use]
-    fn ntt(self) -> Self::Output {
-        let mut r = Poly(self.0);
+    fn inverse_ntt_in_place_scalar(&mut self) {
+        let r = self;
 
         let mut j;
-        let mut k = 127;
+        let mut k = Self::DEGREE / 2 - 1;
         let mut len = 2;
 
-        while len <= 128 {
+        while len <= Self::DEGREE / 2 {
             let mut start = 0;
-            while start < 256 {
+            while start < Self::DEGREE {
                 let zeta = Coefficient(ZETAS[k]);
                 k -= 1;
                 j = start;
                 while j < (start + len) {
                     let t = r[j];
                     r[j] = t + r[j + len];
-                    r[j + len] = r[j + len] - t;
+                    r[j + len] -= t;
                     r[j + len] = zeta * r[j + len];
                     j += 1;
                 }
@@ -270,10 +618,440 @@ impl Ntt for Poly<32, false> {
             }
             len <<= 1;
         }
-        for j in 0..256 {
-            r[j] = r[j] * Coefficient::F;
-        }
+        *r *= Self::F;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use sha3::digest::XofReader;
+    use subtle::ConstantTimeEq;
+
+    use super::{Poly, PolyBlock, PolyMul, Coefficient, Ntt, Message, DecodeError};
+
+    fn random<const SIZE: usize, const B: bool>(bound: i16) -> Poly<SIZE, B> {
+        let mut rng = rand::thread_rng();
+        let mut it = core::iter::repeat_with(|| Coefficient(rng.gen_range(-bound..bound)));
+        Poly((0..SIZE).map(|_| PolyBlock::new(&mut it)).collect())
+    }
 
+    // the product modulo `X^n + 1` computed directly
+    fn schoolbook<const SIZE: usize>(a: &Poly<SIZE, true>, b: &Poly<SIZE, true>) -> [i64; 256] {
+        let n = SIZE * 8;
+        let q = i64::from(Coefficient::Q);
+        let mut r = [0; 256];
+        for i in 0..n {
+            for j in 0..n {
+                let x = i64::from(a[i].0) * i64::from(b[j].0);
+                if i + j < n {
+                    r[i + j] += x;
+                } else {
+                    r[i + j - n] -= x;
+                }
+            }
+        }
+        for x in &mut r[..n] {
+            *x = x.rem_euclid(q);
+        }
         r
     }
+
+    fn ntt_round_trip<const SIZE: usize>() {
+        // `2^16` modulo `q`, the round trip is scaled by it
+        const R: i64 = 2285;
+
+        let q = i64::from(Coefficient::Q);
+        for _ in 0..100 {
+            let p = random::<SIZE, true>(Coefficient::Q);
+            let r = p.ntt().ntt();
+            for i in 0..(SIZE * 8) {
+                let expected = (i64::from(p[i].0) * R).rem_euclid(q);
+                assert_eq!(i64::from(r[i].pack()), expected);
+            }
+
+            // the other way, the inverse first, the inverse carries the scaling
+            let p = random::<SIZE, false>(Coefficient::Q);
+            let r = p.ntt().ntt();
+            for i in 0..(SIZE * 8) {
+                let expected = (i64::from(p[i].0) * R).rem_euclid(q);
+                assert_eq!(i64::from(r[i].pack()), expected);
+            }
+
+            let a = random::<SIZE, true>(Coefficient::Q);
+            let b = random::<SIZE, true>(Coefficient::Q);
+            let c = a.ntt().mul_montgomery(&b.ntt()).ntt();
+            let expected = schoolbook(&a, &b);
+            for i in 0..(SIZE * 8) {
+                assert_eq!(i64::from(c[i].pack()), expected[i]);
+            }
+        }
+    }
+
+    // the remainders modulo `X^2 - zeta^(2 j + 1)` by the definition
+    fn check_natural<const SIZE: usize>() {
+        let q = i64::from(Coefficient::Q);
+        let n = SIZE * 8;
+        let pow = |mut x: i64, mut e: usize| {
+            let mut r = 1;
+            while e > 0 {
+                if e & 1 == 1 {
+                    r = r * x % q;
+                }
+                x = x * x % q;
+                e >>= 1;
+            }
+            r
+        };
+        let zeta = pow(17, 256 / n);
+
+        let p = random::<SIZE, true>(Coefficient::Q);
+        let t = p.ntt();
+        assert_eq!(t.iter_natural().count(), n);
+        for (j, pair) in t
+            .iter_natural()
+            .collect::<std::vec::Vec<_>>()
+            .chunks(2)
+            .enumerate()
+        {
+            let r = pow(zeta, 2 * j + 1);
+            let (mut even, mut odd, mut rk) = (0, 0, 1);
+            for k in 0..(n / 2) {
+                even = (even + i64::from(p[2 * k].0) * rk).rem_euclid(q);
+                odd = (odd + i64::from(p[2 * k + 1].0) * rk).rem_euclid(q);
+                rk = rk * r % q;
+            }
+            assert_eq!(i64::from(pair[0].pack()), even, "{j}");
+            assert_eq!(i64::from(pair[1].pack()), odd, "{j}");
+        }
+    }
+
+    // the 12 bit packing, two coefficients in three bytes, the nibbles of the middle byte
+    // are the high part of the first and the low part of the second
+    fn bytes_round_trip<const SIZE: usize>() {
+        struct UpdateVec(std::vec::Vec<u8>);
+
+        impl sha3::digest::Update for UpdateVec {
+            fn update(&mut self, data: &[u8]) {
+                self.0.extend_from_slice(data);
+            }
+        }
+
+        for _ in 0..100 {
+            // signed representatives, the bytes hold the canonical ones
+            let p = random::<SIZE, false>(Coefficient::Q);
+            let mut bytes = UpdateVec(std::vec::Vec::new());
+            p.to_bytes(&mut bytes);
+            assert_eq!(bytes.0.len(), SIZE * 12);
+            assert!(p.byte_iter().eq(bytes.0.iter().copied()));
+
+            let r = Poly::<SIZE, false>::from_bytes_checked(&bytes.0).unwrap();
+            assert!(Poly::<SIZE, false>::from_bytes(&bytes.0).unwrap() == r);
+            for i in 0..(SIZE * 8) {
+                assert_eq!(r[i].0, p[i].pack() as i16);
+            }
+            assert!(bool::from(r.ct_eq(&p)));
+        }
+
+        let mut it = [0x123, 0x456, 0xcba, 0x987]
+            .map(Coefficient)
+            .into_iter()
+            .cycle();
+        let p = Poly::<SIZE, false>((0..SIZE).map(|_| PolyBlock::new(&mut it)).collect());
+        let bytes = p.byte_iter().collect::<std::vec::Vec<_>>();
+        assert_eq!(bytes[..6], [0x23, 0x61, 0x45, 0xba, 0x7c, 0x98]);
+        assert!(Poly::<SIZE, false>::from_bytes(&bytes).unwrap() == p);
+    }
+
+    #[test]
+    fn bytes_round_trip_all() {
+        bytes_round_trip::<1>();
+        bytes_round_trip::<2>();
+        bytes_round_trip::<4>();
+        bytes_round_trip::<8>();
+        bytes_round_trip::<16>();
+        bytes_round_trip::<32>();
+    }
+
+    #[test]
+    fn natural_order() {
+        check_natural::<1>();
+        check_natural::<2>();
+        check_natural::<4>();
+        check_natural::<8>();
+        check_natural::<16>();
+        check_natural::<32>();
+    }
+
+    #[test]
+    fn ntt_small_ring() {
+        ntt_round_trip::<1>();
+        ntt_round_trip::<2>();
+        ntt_round_trip::<4>();
+        ntt_round_trip::<8>();
+        ntt_round_trip::<16>();
+        ntt_round_trip::<32>();
+    }
+
+    #[test]
+    fn conditional_select() {
+        use subtle::{Choice, ConditionallySelectable};
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let a = random::<32, false>(Coefficient::Q);
+            let b = random::<32, false>(Coefficient::Q);
+            let bit = rng.gen::<bool>();
+            let expected = if bit { b } else { a };
+            assert!(Poly::conditional_select(&a, &b, Choice::from(u8::from(bit))) == expected);
+
+            let mut c = a;
+            c.conditional_assign(&b, Choice::from(u8::from(bit)));
+            assert!(c == expected);
+        }
+    }
+
+    #[test]
+    fn add_sub_operator() {
+        let a = random::<32, true>(Coefficient::Q);
+        let b = random::<32, true>(Coefficient::Q);
+
+        let mut c = a;
+        c += &b;
+        assert!(&a + &b == c);
+
+        let mut d = a;
+        d -= &b;
+        assert!(&a - &b == d);
+        assert!(bool::from((&(&a + &b) - &b).ct_eq(&a)));
+    }
+
+    #[test]
+    fn mul_operator() {
+        let a = random::<32, false>(Coefficient::Q);
+        let b = random::<32, false>(Coefficient::Q);
+        let c = &a * &b;
+        assert!(c == a.mul_montgomery(&b));
+
+        let mut d = a;
+        d *= &b;
+        assert!(d == c);
+
+        let r = a.mul_reduce(&b);
+        assert!(bool::from(r.ct_eq(&c)));
+        assert!(r == c.barrett_reduce());
+    }
+
+    #[test]
+    fn barrett_reduce() {
+        for _ in 0..1000 {
+            // the reduction is correct for the absolute value up to about `9.5 * q`
+            let p = random::<32, true>(9 * Coefficient::Q);
+            let mut q = p;
+            for i in 0..256 {
+                q[i] = Coefficient::barrett_reduce(q[i].0);
+            }
+            assert!(p.barrett_reduce() == q);
+        }
+    }
+
+    #[test]
+    fn zero() {
+        let p = random::<32, true>(Coefficient::Q);
+        let mut q = p;
+        q -= &p;
+        assert!(bool::from(q.ct_is_zero()));
+        assert!(bool::from(Poly::<32, true>::zero().ct_is_zero()));
+        assert!(bool::from(q.ct_eq(&Poly::zero())));
+
+        let mut q = Poly::<32, true>::zero();
+        q[255] = Coefficient(1);
+        assert!(!bool::from(q.ct_is_zero()));
+    }
+
+    #[test]
+    fn mul_scalar() {
+        // `2^-16` modulo `q`
+        const R_INV: i64 = 169;
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let p = random::<32, true>(Coefficient::Q);
+            let c = Coefficient(rng.gen_range(-Coefficient::Q..Coefficient::Q));
+            let r = p * c;
+            for i in 0..256 {
+                let expected = (i64::from(p[i].0) * i64::from(c.0) * R_INV)
+                    .rem_euclid(i64::from(Coefficient::Q));
+                assert_eq!(i64::from(r[i].pack()), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn compress_1() {
+        use std::vec::Vec;
+
+        use sha3::digest::Update;
+
+        struct Sink(Vec<u8>);
+
+        impl Update for Sink {
+            fn update(&mut self, data: &[u8]) {
+                self.0.extend_from_slice(data);
+            }
+        }
+
+        for _ in 0..100 {
+            let msg = rand::random::<[u8; 32]>();
+            let p = Poly::<32, true>::from_msg(&Message(msg));
+            let mut sink = Sink(Vec::new());
+            p.compress::<_, 1>(&mut sink);
+            assert_eq!(sink.0, msg);
+            assert!(Poly::<32, true>::decompress::<1>(&sink.0) == p);
+        }
+    }
+
+    // a stream of consecutive bytes, the samples are known in advance
+    struct Counter(u8);
+
+    impl XofReader for Counter {
+        fn read(&mut self, buffer: &mut [u8]) {
+            for b in buffer {
+                *b = self.0;
+                self.0 = self.0.wrapping_add(1);
+            }
+        }
+    }
+
+    #[test]
+    fn uniform_from_reader() {
+        let p = Poly::<32, false>::uniform(Counter(0));
+
+        let mut bytes = Counter(0);
+        let mut expected = std::vec::Vec::<i16>::new();
+        while expected.len() < 256 {
+            let mut b = [0; 3];
+            bytes.read(&mut b);
+            let d1 = i16::from(b[0]) | (i16::from(b[1] & 0xf) << 8);
+            let d2 = i16::from(b[1] >> 4) | (i16::from(b[2]) << 4);
+            expected.extend([d1, d2].iter().filter(|d| **d < Coefficient::Q).copied());
+        }
+        for (i, d) in expected.into_iter().take(256).enumerate() {
+            assert_eq!(p[i].0, d);
+        }
+    }
+
+    #[test]
+    fn noise_from_reader() {
+        // the groups of bits `0b11` and `0b00` alternate, the coefficients of eta 2 are `2 - 0`
+        let p = Poly::<32, true>::noise::<_, 4>(Pattern);
+        for i in 0..256 {
+            assert_eq!(p[i].0, 2);
+        }
+    }
+
+    struct Pattern;
+
+    impl XofReader for Pattern {
+        fn read(&mut self, buffer: &mut [u8]) {
+            buffer.fill(0b0011_0011);
+        }
+    }
+
+    // random bytes with the bits of the mask set, `0xcc` rejects three quarters
+    // of the candidates, so the samplers need more than three blocks
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    struct Biased(u8);
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    impl XofReader for Biased {
+        fn read(&mut self, buffer: &mut [u8]) {
+            let mut rng = rand::thread_rng();
+            for b in buffer {
+                *b = rng.gen::<u8>() | self.0;
+            }
+        }
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    #[test]
+    fn avx2_uniform_matches_scalar() {
+        use super::avx2;
+
+        if !avx2::available() {
+            return;
+        }
+        for mask in [0x00, 0x08, 0x0c, 0xc8, 0xcc] {
+            for _ in 0..200 {
+                // the same stream for both samplers
+                let mut stream = [0; 8192];
+                Biased(mask).read(&mut stream);
+
+                let p = unsafe { Poly::<32, false>::uniform_avx2(Replay(&stream)) };
+                assert!(p == Poly::uniform_scalar(Replay(&stream)));
+                let p = unsafe { Poly::<2, false>::uniform_avx2(Replay(&stream)) };
+                assert!(p == Poly::uniform_scalar(Replay(&stream)));
+            }
+        }
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    struct Replay<'a>(&'a [u8]);
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    impl XofReader for Replay<'_> {
+        fn read(&mut self, buffer: &mut [u8]) {
+            let (head, tail) = self.0.split_at(buffer.len());
+            buffer.clone_from_slice(head);
+            self.0 = tail;
+        }
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    #[test]
+    fn avx2_matches_scalar() {
+        use super::avx2;
+
+        if !avx2::available() {
+            return;
+        }
+        for _ in 0..1000 {
+            let p = random::<32, true>(Coefficient::Q);
+            let mut q = p;
+            q.ntt_in_place_scalar();
+            assert!(p.ntt().0 == q.0);
+            let p = random::<32, false>(Coefficient::Q);
+            let mut q = p;
+            q.inverse_ntt_in_place_scalar();
+            assert!(p.ntt().0 == q.0);
+        }
+    }
+
+    #[test]
+    fn from_bytes_length() {
+        let mut rng = rand::thread_rng();
+        let mut it = core::iter::repeat_with(|| Coefficient(rng.gen_range(0..Coefficient::Q)));
+        let p = Poly::<32, false>((0..32).map(|_| PolyBlock::new(&mut it)).collect());
+        let mut bytes = [0; 385];
+        for (chunk, b) in bytes.chunks_mut(12).zip(&p.0) {
+            chunk.clone_from_slice(&b.to_bytes());
+        }
+        assert!(Poly::<32, false>::from_bytes_checked(&bytes[..384]).unwrap() == p);
+        assert!(Poly::<32, false>::from_bytes(&bytes[..384]).unwrap() == p);
+
+        for len in [0, 12, 383, 385] {
+            let expected = Some(DecodeError::UnexpectedLength {
+                expected: 384,
+                got: len,
+            });
+            assert_eq!(Poly::<32, false>::from_bytes(&bytes[..len]).err(), expected);
+            assert_eq!(
+                Poly::<32, false>::from_bytes_checked(&bytes[..len]).err(),
+                expected
+            );
+        }
+        // the smaller ring, one block
+        assert!(Poly::<1, false>::from_bytes_checked(&bytes[..11]).is_err());
+        assert!(Poly::<1, false>::from_bytes_checked(&bytes[..12]).is_ok());
+    }
 }
@@ -0,0 +1,139 @@
+//! The symmetric primitives Kyber is instantiated with. The standard variant uses SHA-3,
+//! the 90s variant uses AES-256 in counter mode and SHA-2.
+
+use sha3::{
+    Sha3_256, Sha3_512, Shake128, Shake256,
+    digest::{
+        Update, FixedOutput, ExtendableOutput, XofReader,
+        consts::{U32, U64},
+    },
+};
+
+pub trait Symmetric {
+    /// The stream the element of the matrix is sampled from.
+    type Xof: XofReader;
+
+    /// The stream the noise is sampled from.
+    type Prf: XofReader;
+
+    /// The hash function `H`.
+    type H: Default + FixedOutput<OutputSize = U32>;
+
+    /// The hash function `G`.
+    type G: Default + FixedOutput<OutputSize = U64>;
+
+    /// `XOF(seed, i, j)`
+    fn xof(seed: &[u8; 32], i: u8, j: u8) -> Self::Xof;
+
+    /// `PRF(key, nonce)`
+    fn prf(key: &[u8; 32], nonce: u8) -> Self::Prf;
+
+    /// Derives the shared secret from the pre-key, the hash of the cipher text
+    /// and the context, the empty context is the standard derivation.
+    /// Fills the whole `ss`, 32 bytes is the standard length.
+    fn kdf(key: &[u8; 32], ct_hash: &[u8; 32], context: &[u8], ss: &mut [u8]);
+}
+
+/// SHAKE128, SHAKE256, SHA3-256 and SHA3-512.
+pub struct Shake;
+
+impl Symmetric for Shake {
+    type Xof = <Shake128 as ExtendableOutput>::Reader;
+
+    type Prf = <Shake256 as ExtendableOutput>::Reader;
+
+    type H = Sha3_256;
+
+    type G = Sha3_512;
+
+    #[inline]
+    fn xof(seed: &[u8; 32], i: u8, j: u8) -> Self::Xof {
+        Shake128::default().chain(seed).chain([i, j]).finalize_xof()
+    }
+
+    #[inline]
+    fn prf(key: &[u8; 32], nonce: u8) -> Self::Prf {
+        Shake256::default().chain(key).chain([nonce]).finalize_xof()
+    }
+
+    #[inline]
+    fn kdf(key: &[u8; 32], ct_hash: &[u8; 32], context: &[u8], ss: &mut [u8]) {
+        let mut xof = Shake256::default()
+            .chain(key)
+            .chain(ct_hash)
+            .chain(context)
+            .finalize_xof();
+        xof.read(ss);
+    }
+}
+
+#[cfg(feature = "kyber90s")]
+pub use self::aes_ctr::Aes;
+
+#[cfg(feature = "kyber90s")]
+mod aes_ctr {
+    use aes::{
+        Aes256,
+        cipher::{KeyIvInit, StreamCipher},
+    };
+    use sha2::{
+        Sha256, Sha512,
+        digest::{Update, FixedOutput},
+    };
+    use sha3::digest::XofReader;
+
+    use super::Symmetric;
+
+    /// AES-256 in counter mode, SHA-256 and SHA-512.
+    pub struct Aes;
+
+    /// The key stream of AES-256, the nonce takes 12 bytes of the block,
+    /// the rest is the 32 bit big endian counter starting from zero.
+    pub struct AesCtr(ctr::Ctr32BE<Aes256>);
+
+    impl AesCtr {
+        fn new(key: &[u8; 32], nonce: [u8; 2]) -> Self {
+            let mut iv = [0; 16];
+            iv[..2].clone_from_slice(&nonce);
+            AesCtr(ctr::Ctr32BE::new(key.into(), &iv.into()))
+        }
+    }
+
+    impl XofReader for AesCtr {
+        fn read(&mut self, buffer: &mut [u8]) {
+            buffer.fill(0);
+            self.0.apply_keystream(buffer);
+        }
+    }
+
+    impl Symmetric for Aes {
+        type Xof = AesCtr;
+
+        type Prf = AesCtr;
+
+        type H = Sha256;
+
+        type G = Sha512;
+
+        #[inline]
+        fn xof(seed: &[u8; 32], i: u8, j: u8) -> Self::Xof {
+            AesCtr::new(seed, [i, j])
+        }
+
+        #[inline]
+        fn prf(key: &[u8; 32], nonce: u8) -> Self::Prf {
+            AesCtr::new(key, [nonce, 0])
+        }
+
+        // SHA-256 has the fixed output, the 90s variant only derives 32 bytes
+        #[inline]
+        fn kdf(key: &[u8; 32], ct_hash: &[u8; 32], context: &[u8], ss: &mut [u8]) {
+            let digest = Sha256::default()
+                .chain(key)
+                .chain(ct_hash)
+                .chain(context)
+                .finalize_fixed();
+            ss.clone_from_slice(&digest);
+        }
+    }
+}
@@ -5,14 +5,31 @@ use std::{
     vec::Vec,
 };
 
-use sha3::digest::Update;
+use sha3::{
+    Sha3_256,
+    digest::{Update, FixedOutput},
+};
 use serde::{Serialize, Deserialize};
 
 use super::{
+    coefficient::Coefficient,
     config::{Dim, Config},
-    kem::{KeySeed, key_pair, encapsulate, decapsulate},
+    kem::{
+        KeySeed, SecretKey, PublicKey, CipherText, DecodeError, Kyber768, SHARED_SECRET_BYTES,
+        key_pair, load_key_pair, try_load_key_pair, encapsulate, decapsulate, mlkem,
+    },
 };
 
+mod kat;
+
+struct UpdateVec(Vec<u8>);
+
+impl Update for UpdateVec {
+    fn update(&mut self, data: &[u8]) {
+        self.0.extend_from_slice(data);
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct Vector<const DIM: usize> {
     main: String,
@@ -26,17 +43,17 @@ struct Vector<const DIM: usize> {
 
 #[test]
 fn test_2() {
-    test::<2>()
+    test::<2>();
 }
 
 #[test]
 fn test_3() {
-    test::<3>()
+    test::<3>();
 }
 
 #[test]
 fn test_4() {
-    test::<4>()
+    test::<4>();
 }
 
 fn test<const DIM: usize>()
@@ -59,7 +76,7 @@ where
         });
         let mut lines = BufReader::new(file)
             .lines()
-            .filter_map(Result::ok)
+            .map_while(Result::ok)
             .take_while(|a| !a.is_empty());
         let mut i = 0;
         while i < limit {
@@ -107,14 +124,6 @@ where
     }
 
     fn check(&self, i: usize) {
-        struct UpdateVec(Vec<u8>);
-
-        impl Update for UpdateVec {
-            fn update(&mut self, data: &[u8]) {
-                self.0.extend_from_slice(data);
-            }
-        }
-
         let main = hex::decode(&self.main).unwrap().try_into().unwrap();
         let reject = hex::decode(&self.reject).unwrap().try_into().unwrap();
         let (sk, pk) = key_pair::<DIM>(KeySeed { main, reject });
@@ -128,9 +137,1651 @@ where
         ct.to_bytes(&mut v);
         assert_eq!(self.ct, hex::encode(v.0), "{i}");
 
-        assert_eq!(self.ss, hex::encode(&ss), "{i}");
+        assert_eq!(self.ss, hex::encode(ss), "{i}");
 
         let ss = decapsulate(&sk, &pk, &ct);
-        assert_eq!(self.ss, hex::encode(&ss), "{i}");
+        assert_eq!(self.ss, hex::encode(ss), "{i}");
+    }
+}
+
+#[test]
+fn mlkem_2() {
+    mlkem::<2>([
+        "82f101ff648063b376e2bb6c5b7455f655a50c2feadade150efa0e0e6f365aea",
+        "e3fdddb90255869185c07cdf1c1880b2efe08b6f04da4997b693c0dea61503bd",
+        "14cace3e48771b316676afad2cfcfe8488daaa4fad954e57236caa3f24a42cf7",
+        "fc3ccf20a37dd91d9f511da5e6cb92039b1414db5b0880741178ccfb143212ff",
+    ]);
+}
+
+#[test]
+fn mlkem_3() {
+    mlkem::<3>([
+        "a24e16d8f8f9383a95b77050f4d9fd2f5733eec1d63ef3c23ebf9918173669a7",
+        "b4cfbd24cef67afd3764276c6980e0f88f8e9ca57f59b7f12fe1a9c1e72f4710",
+        "9cddd089ffe70e3996e76f7c8d06746df34d07e8657bc0fcf2bb0e1c3084aea1",
+        "c8fbeddafdacef2ffeb8b354ea644f11b5c150f3e2c4a74ce38abba8f854ae16",
+    ]);
+}
+
+#[test]
+fn mlkem_4() {
+    mlkem::<4>([
+        "61349e5c131a7e116a0463861d7d18663c5627c38c7147ddaadfd48acd7a4535",
+        "c1579fa02c614f3762b2a799b51e41cebb8f820f34fa736af02c56de2460ce3c",
+        "0ad8d1ea1b8dd788979b4379581218df9321bdce5567eca42ae6be7d395f1a54",
+        "294cb5b6b0f37a047745a2a69991f66ac85936e1b077e717eaeb26bb0ba42c68",
+    ]);
+}
+
+// `d`, `z` and `m` are consecutive bytes, expected values are hashes of the public key
+// and the cipher text, the shared secret and the implicit rejection of zero cipher text
+fn mlkem<const DIM: usize>(expected: [&str; 4])
+where
+    Dim<DIM>: Config<32>,
+{
+    let main = (0..32).collect::<Vec<u8>>().try_into().unwrap();
+    let reject = (32..64).collect::<Vec<u8>>().try_into().unwrap();
+    let m = (64..96).collect::<Vec<u8>>().try_into().unwrap();
+
+    let (sk, pk) = mlkem::key_pair::<DIM>(KeySeed { main, reject });
+    let mut sha = Sha3_256::default();
+    pk.to_bytes(&mut sha);
+    assert_eq!(expected[0], hex::encode(sha.finalize_fixed()));
+
+    let (ct, ss) = mlkem::encapsulate(m, &pk);
+    let mut sha = Sha3_256::default();
+    ct.to_bytes(&mut sha);
+    assert_eq!(expected[1], hex::encode(sha.finalize_fixed()));
+    assert_eq!(expected[2], hex::encode(ss));
+    assert_eq!(ss, mlkem::decapsulate(&sk, &pk, &ct));
+
+    let mut v = UpdateVec(vec![]);
+    ct.to_bytes(&mut v);
+    let ct = CipherText::<DIM>::from_bytes(&vec![0; v.0.len()]);
+    assert_eq!(expected[3], hex::encode(mlkem::decapsulate(&sk, &pk, &ct)));
+}
+
+#[test]
+fn kyber768_round_trip() {
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, ss) = Kyber768::encapsulate([3; 32], &pk);
+    assert_eq!(ss, Kyber768::decapsulate(&sk, &pk, &ct));
+    assert_eq!(ss.len(), Kyber768::SHARED_SECRET_BYTES);
+}
+
+#[test]
+fn variant() {
+    use super::kem::{Variant, AnyPublicKey};
+
+    for dim in 2..=4 {
+        let variant = Variant::from_dim(dim).unwrap();
+        assert_eq!(variant.dim(), dim);
+
+        let (sk, pk) = variant.key_pair(KeySeed {
+            main: [1; 32],
+            reject: [2; 32],
+        });
+        assert_eq!(sk.variant(), variant);
+
+        let mut v = UpdateVec(vec![]);
+        pk.to_bytes(&mut v);
+        assert_eq!(v.0.len(), variant.public_key_bytes());
+        let pk = variant.try_public_key_from_bytes(&v.0).unwrap();
+
+        let (ct, ss) = variant.encapsulate([3; 32], &pk).unwrap();
+        let mut v = UpdateVec(vec![]);
+        ct.to_bytes(&mut v);
+        assert_eq!(v.0.len(), variant.cipher_text_bytes());
+        let ct = variant.try_cipher_text_from_bytes(&v.0).unwrap();
+        assert_eq!(Some(ss), variant.decapsulate(&sk, &pk, &ct));
+
+        let mut v = UpdateVec(vec![]);
+        sk.to_bytes(&pk, &mut v).unwrap();
+        assert_eq!(v.0.len(), variant.secret_key_bytes());
+        let (sk, pk) = variant.try_load_key_pair(&v.0).unwrap();
+        assert_eq!(Some(ss), variant.decapsulate(&sk, &pk, &ct));
+    }
+    assert_eq!(Variant::from_dim(5), None);
+
+    let (_, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (_, ss) = encapsulate([3; 32], &pk);
+    let pk = AnyPublicKey::Kyber768(pk);
+    assert_eq!(Variant::Kyber768.encapsulate([3; 32], &pk).unwrap().1, ss);
+    assert!(Variant::Kyber512.encapsulate([3; 32], &pk).is_none());
+
+    // the keys of different parameter sets are not a key pair
+    let (sk, _) = Variant::Kyber512.key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let mut v = UpdateVec(vec![]);
+    assert!(sk.to_bytes(&pk, &mut v).is_none());
+    assert!(v.0.is_empty());
+}
+
+#[test]
+fn lengths() {
+    check_lengths::<2>();
+    check_lengths::<3>();
+    check_lengths::<4>();
+}
+
+fn check_lengths<const DIM: usize>()
+where
+    Dim<DIM>: Config<32>,
+{
+    let (sk, pk) = key_pair::<DIM>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+
+    let mut v = UpdateVec(vec![]);
+    pk.to_bytes(&mut v);
+    assert_eq!(v.0.len(), PublicKey::<DIM>::BYTES);
+
+    let (ct, _) = encapsulate([3; 32], &pk);
+    let mut v = UpdateVec(vec![]);
+    ct.to_bytes(&mut v);
+    assert_eq!(v.0.len(), CipherText::<DIM>::BYTES);
+
+    let ss = decapsulate(&sk, &pk, &ct);
+    assert_eq!(ss.len(), SHARED_SECRET_BYTES);
+
+    // asserts the length internally
+    let mut v = UpdateVec(vec![]);
+    sk.to_bytes(&pk, &mut v);
+    let _ = load_key_pair::<DIM>(&v.0);
+}
+
+#[test]
+fn decode_errors() {
+    assert_eq!(
+        PublicKey::<3>::try_from_bytes(&[0; 100]).err(),
+        Some(DecodeError::UnexpectedLength {
+            expected: PublicKey::<3>::BYTES,
+            got: 100,
+        }),
+    );
+    assert_eq!(
+        CipherText::<3>::try_from_bytes(&[0; 100]).err(),
+        Some(DecodeError::UnexpectedLength {
+            expected: CipherText::<3>::BYTES,
+            got: 100,
+        }),
+    );
+    assert_eq!(
+        try_load_key_pair::<3>(&[0; 100]).err(),
+        Some(DecodeError::UnexpectedLength {
+            expected: SecretKey::<3>::BYTES,
+            got: 100,
+        }),
+    );
+
+    // the first coefficient is 0xfff
+    let mut b = vec![0; PublicKey::<3>::BYTES];
+    b[0] = 0xff;
+    b[1] = 0x0f;
+    assert_eq!(
+        PublicKey::<3>::try_from_bytes(&b).err(),
+        Some(DecodeError::NonCanonicalCoefficient),
+    );
+    b[1] = 0x00;
+    assert!(PublicKey::<3>::try_from_bytes(&b).is_ok());
+}
+
+// FIPS 203 modulus check, the key is accepted if and only if it encodes back unchanged
+#[test]
+fn modulus_check() {
+    let (_, pk) = key_pair::<2>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let bytes = pk.to_array::<{ PublicKey::<2>::BYTES }>();
+    let decoded = PublicKey::<2>::try_from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.to_array::<{ PublicKey::<2>::BYTES }>(), bytes);
+
+    // every value of `q..4096` in both coefficients of the middle triple
+    let i = PublicKey::<2>::BYTES / 2 / 3 * 3;
+    for x in (Coefficient::Q as u16 - 1)..0x1000 {
+        let mut b = bytes;
+        b[i] = x as u8;
+        b[i + 1] = (b[i + 1] & 0xf0) | (x >> 8) as u8;
+        let mut c = bytes;
+        c[i + 1] = (c[i + 1] & 0x0f) | (x << 4) as u8;
+        c[i + 2] = (x >> 4) as u8;
+        for b in [b, c] {
+            let result = PublicKey::<2>::try_from_bytes(&b);
+            if x < Coefficient::Q as u16 {
+                let encoded = result.unwrap().to_array::<{ PublicKey::<2>::BYTES }>();
+                assert_eq!(encoded, b);
+            } else {
+                assert_eq!(result.err(), Some(DecodeError::NonCanonicalCoefficient));
+            }
+        }
+    }
+}
+
+#[test]
+fn cipher_text_length() {
+    check_cipher_text_length::<2>();
+    check_cipher_text_length::<3>();
+    check_cipher_text_length::<4>();
+}
+
+fn check_cipher_text_length<const DIM: usize>()
+where
+    Dim<DIM>: Config<32>,
+{
+    let n = CipherText::<DIM>::BYTES;
+    for len in [0, n - 1, n + 1] {
+        assert_eq!(
+            CipherText::<DIM>::try_from_bytes(&vec![0; len]).err(),
+            Some(DecodeError::UnexpectedLength {
+                expected: n,
+                got: len,
+            }),
+        );
+    }
+
+    for _ in 0..100 {
+        let b = (0..n).map(|_| rand::random()).collect::<Vec<u8>>();
+        let mut v = UpdateVec(vec![]);
+        CipherText::<DIM>::try_from_bytes(&b)
+            .unwrap()
+            .to_bytes(&mut v);
+        assert_eq!(v.0, b);
+    }
+}
+
+#[test]
+fn public_key_builder() {
+    use super::kem::PublicKeyBuilder;
+
+    let (_, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let bytes = pk.to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>();
+
+    for chunk in [1, 5, 12, 100, bytes.len()] {
+        let mut builder = PublicKeyBuilder::<3>::new();
+        bytes.chunks(chunk).for_each(|c| builder.update(c));
+        let decoded = builder.finish().unwrap();
+        assert!(decoded == pk);
+        assert_eq!(decoded.hash(), pk.hash());
+    }
+
+    let mut builder = PublicKeyBuilder::<3>::new();
+    builder.update(&bytes[1..]);
+    assert_eq!(
+        builder.finish().err(),
+        Some(DecodeError::UnexpectedLength {
+            expected: PublicKey::<3>::BYTES,
+            got: PublicKey::<3>::BYTES - 1,
+        }),
+    );
+
+    // the first coefficient is 0xfff
+    let mut b = bytes;
+    b[0] = 0xff;
+    b[1] |= 0x0f;
+    let mut builder = PublicKeyBuilder::<3>::new();
+    builder.update(&b);
+    assert_eq!(
+        builder.finish().err(),
+        Some(DecodeError::NonCanonicalCoefficient),
+    );
+}
+
+#[test]
+fn try_from_slice() {
+    fn parse(pk: &[u8], ct: &[u8]) -> Result<(PublicKey<3>, CipherText<3>), DecodeError> {
+        Ok((pk.try_into()?, ct.try_into()?))
+    }
+
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, ss) = encapsulate([3; 32], &pk);
+    let pk_bytes = pk.to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>();
+    let ct_bytes = ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>();
+
+    let (decoded_pk, decoded_ct) = parse(&pk_bytes, &ct_bytes).unwrap();
+    assert!(decoded_pk == pk);
+    assert_eq!(ss, decapsulate(&sk, &decoded_pk, &decoded_ct));
+
+    assert_eq!(
+        parse(&pk_bytes[1..], &ct_bytes).err(),
+        Some(DecodeError::UnexpectedLength {
+            expected: PublicKey::<3>::BYTES,
+            got: PublicKey::<3>::BYTES - 1,
+        }),
+    );
+    assert_eq!(
+        parse(&pk_bytes, &ct_bytes[1..]).err(),
+        Some(DecodeError::UnexpectedLength {
+            expected: CipherText::<3>::BYTES,
+            got: CipherText::<3>::BYTES - 1,
+        }),
+    );
+}
+
+#[cfg(feature = "kem")]
+#[test]
+fn kem_traits() {
+    use kem::{Encapsulate, Decapsulate};
+
+    use super::kem::KeyPair;
+
+    let key_pair = KeyPair::<3>::from_seed(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, ss) = key_pair
+        .public_key()
+        .encapsulate(&mut rand::thread_rng())
+        .unwrap();
+    // the inherent `KeyPair::decapsulate` shadows the method of the trait
+    assert_eq!(ss.0, Decapsulate::decapsulate(&key_pair, &ct).unwrap().0);
+}
+
+#[test]
+fn to_array() {
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let pk_array = pk.to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>();
+    let mut v = UpdateVec(vec![]);
+    pk.to_bytes(&mut v);
+    assert_eq!(pk_array.as_ref(), v.0);
+
+    let (ct, ss) = Kyber768::encapsulate([3; 32], &pk);
+    let ct_array = ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>();
+    let mut v = UpdateVec(vec![]);
+    ct.to_bytes(&mut v);
+    assert_eq!(ct_array.as_ref(), v.0);
+
+    let sk_array = sk.to_array::<{ Kyber768::SECRET_KEY_BYTES }>(&pk);
+    let (sk, pk) = Kyber768::load_key_pair(&sk_array);
+    assert_eq!(ss, Kyber768::decapsulate(&sk, &pk, &ct));
+}
+
+#[test]
+fn from_seed() {
+    let seed = KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    };
+    let stored = seed.to_bytes();
+    let (sk, pk) = Kyber768::key_pair(seed);
+    let (ct, ss) = Kyber768::encapsulate([3; 32], &pk);
+    assert_eq!(ss, Kyber768::decapsulate(&sk, &pk, &ct));
+
+    let sk = SecretKey::<3>::from_seed(KeySeed::from_bytes(&stored));
+    assert_eq!(ss, Kyber768::decapsulate(&sk, &pk, &ct));
+
+    // implicit rejection must use the same `reject` seed
+    let (bad_ct, _) = Kyber768::encapsulate([4; 32], &pk);
+    let bad_ct = CipherText::<3>::from_bytes(&{
+        let mut b = bad_ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>();
+        b[0] ^= 1;
+        b
+    });
+    let expected = Kyber768::decapsulate(&sk, &pk, &bad_ct);
+    let sk = SecretKey::<3>::from_seed(KeySeed::from_bytes(&stored));
+    assert_eq!(expected, Kyber768::decapsulate(&sk, &pk, &bad_ct));
+}
+
+#[test]
+fn derive_public() {
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    assert!(pk == sk.derive_public(&[1; 32]));
+    assert!(pk != sk.derive_public(&[3; 32]));
+}
+
+#[test]
+fn ct_eq() {
+    use subtle::ConstantTimeEq;
+
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (other_sk, other_pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [3; 32],
+    });
+    // differs only in the implicit rejection seed
+    assert!(bool::from(pk.ct_eq(&other_pk)));
+    assert!(!bool::from(sk.ct_eq(&other_sk)));
+    assert!(bool::from(sk.ct_eq(&sk.clone())));
+
+    let (other_sk, other_pk) = Kyber768::key_pair(KeySeed {
+        main: [4; 32],
+        reject: [2; 32],
+    });
+    assert!(!bool::from(pk.ct_eq(&other_pk)));
+    assert!(!bool::from(sk.ct_eq(&other_sk)));
+    assert!(pk != other_pk && sk != other_sk);
+
+    // the same key decoded from bytes
+    let decoded = PublicKey::<3>::from_bytes(&pk.to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>());
+    assert!(bool::from(pk.ct_eq(&decoded)));
+    assert!(pk == decoded);
+}
+
+#[test]
+fn implicit_rejection_any_coefficient() {
+    use sha3::{
+        Shake256,
+        digest::{ExtendableOutput, XofReader},
+    };
+
+    let reject = [2; 32];
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject,
+    });
+    let (ct, ss) = Kyber768::encapsulate([3; 32], &pk);
+
+    // the lowest bit of the coefficient 700 of `u`, 10 bits per coefficient, and of
+    // the coefficient 254 of `v`, 4 bits per coefficient, the decrypted message is the same,
+    // only the comparison with the re-encrypted cipher text rejects it
+    for position in [700 * 10 / 8, 3 * 320 + 254 * 4 / 8] {
+        let mut b = ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>();
+        b[position] ^= 1;
+        let rejected = Kyber768::decapsulate(&sk, &pk, &CipherText::<3>::from_bytes(&b));
+        assert_ne!(ss, rejected, "{position}");
+
+        let ct_hash = Sha3_256::default().chain(b).finalize_fixed();
+        let mut expected = [0; 32];
+        Shake256::default()
+            .chain(reject)
+            .chain(ct_hash)
+            .finalize_xof()
+            .read(&mut expected);
+        assert_eq!(expected, rejected, "{position}");
+    }
+}
+
+#[test]
+fn implicit_rejection() {
+    use sha3::{
+        Shake256,
+        digest::{ExtendableOutput, XofReader},
+    };
+
+    let reject = [2; 32];
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject,
+    });
+    let (ct, ss) = Kyber768::encapsulate([3; 32], &pk);
+
+    let mut b = ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>();
+    b[0] ^= 1;
+    let ct = CipherText::<3>::from_bytes(&b);
+    let rejected = Kyber768::decapsulate(&sk, &pk, &ct);
+    assert_ne!(ss, rejected);
+
+    let ct_hash = Sha3_256::default().chain(b).finalize_fixed();
+    let mut expected = [0; 32];
+    Shake256::default()
+        .chain(reject)
+        .chain(ct_hash)
+        .finalize_xof()
+        .read(&mut expected);
+    assert_eq!(expected, rejected);
+}
+
+#[test]
+fn decapsulate_checked() {
+    use super::kem::decapsulate_checked;
+
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, ss) = Kyber768::encapsulate([3; 32], &pk);
+    let (checked, accepted) = decapsulate_checked(&sk, &pk, &ct);
+    assert_eq!(checked, ss);
+    assert!(bool::from(accepted));
+
+    let mut b = ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>();
+    b[0] ^= 1;
+    let ct = CipherText::<3>::from_bytes(&b);
+    let (checked, accepted) = decapsulate_checked(&sk, &pk, &ct);
+    assert_eq!(checked, decapsulate(&sk, &pk, &ct));
+    assert!(!bool::from(accepted));
+}
+
+#[test]
+fn shared_secret_eq() {
+    use super::kem::shared_secret_eq;
+
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, ss) = Kyber768::encapsulate([3; 32], &pk);
+    assert!(bool::from(shared_secret_eq(
+        &ss,
+        &decapsulate(&sk, &pk, &ct)
+    )));
+
+    let mut other = ss;
+    other[31] ^= 1;
+    assert!(!bool::from(shared_secret_eq(&ss, &other)));
+}
+
+#[test]
+fn generate() {
+    let mut rng = rand::thread_rng();
+    let (sk, pk) = Kyber768::generate(&mut rng);
+    let (ct, ss) = Kyber768::encapsulate(rand::random(), &pk);
+    assert_eq!(ss, Kyber768::decapsulate(&sk, &pk, &ct));
+
+    let (_, other_pk) = Kyber768::generate(&mut rng);
+    assert!(pk != other_pk);
+}
+
+#[test]
+fn encapsulate_derand() {
+    use super::kem::encapsulate_derand;
+
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let message = Sha3_256::default().chain([3; 32]).finalize_fixed().into();
+    let (ct, ss) = encapsulate_derand(message, &pk);
+    let (expected_ct, expected_ss) = encapsulate([3; 32], &pk);
+    assert_eq!(ss, expected_ss);
+    assert_eq!(
+        ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>(),
+        expected_ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>(),
+    );
+    assert_eq!(ss, decapsulate(&sk, &pk, &ct));
+}
+
+#[test]
+fn decapsulate_prepared() {
+    use super::kem::decapsulate_prepared;
+
+    let key_pairs = (0..4u8)
+        .map(|i| {
+            Kyber768::key_pair(KeySeed {
+                main: [i; 32],
+                reject: [i + 1; 32],
+            })
+        })
+        .collect::<Vec<_>>();
+    let (ct, ss) = Kyber768::encapsulate([5; 32], &key_pairs[0].1);
+
+    let prepared = ct.prepared();
+    for (sk, pk) in &key_pairs {
+        assert_eq!(
+            decapsulate(sk, pk, &ct),
+            decapsulate_prepared(sk, pk, &prepared)
+        );
+    }
+    assert_eq!(
+        ss,
+        decapsulate_prepared(&key_pairs[0].0, &key_pairs[0].1, &prepared)
+    );
+}
+
+#[test]
+fn secret_key_round_trip() {
+    use subtle::ConstantTimeEq;
+
+    use super::{indcpa, symmetric::Shake};
+
+    let (sk, _) = indcpa::key_pair::<3, 32, Dim<3>, Shake>(&[1; 32]);
+    let mut v = UpdateVec(vec![]);
+    sk.to_bytes(&mut v);
+    let decoded = indcpa::SecretKey::<3, 32>::try_from_bytes(&v.0).unwrap();
+    assert!(bool::from(sk.ct_eq(&decoded)));
+
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let mut v = UpdateVec(vec![]);
+    sk.to_bytes(&pk, &mut v);
+    assert_eq!(v.0.len(), Kyber768::SECRET_KEY_BYTES);
+    let (decoded_sk, decoded_pk) = Kyber768::load_key_pair(&v.0);
+    assert!(sk == decoded_sk && pk == decoded_pk);
+}
+
+#[test]
+fn non_canonical_secret_key() {
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let mut b = sk.to_array::<{ Kyber768::SECRET_KEY_BYTES }>(&pk);
+    // the last coefficient of the first polynomial is `q`
+    b[382] = (b[382] & 0x0f) | ((Coefficient::Q as u8 & 0x0f) << 4);
+    b[383] = (Coefficient::Q >> 4) as u8;
+    assert_eq!(
+        try_load_key_pair::<3>(&b).err(),
+        Some(DecodeError::NonCanonicalCoefficient),
+    );
+    b[383] -= 1;
+    assert!(try_load_key_pair::<3>(&b).is_ok());
+}
+
+// the stored hash of the public key must match, `Ord` and `Hash` of the key use it
+#[test]
+fn key_pair_hash_mismatch() {
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let mut b = sk.to_array::<{ Kyber768::SECRET_KEY_BYTES }>(&pk);
+    let hash_start = Kyber768::SECRET_KEY_BYTES - 64;
+    b[hash_start] ^= 1;
+    assert_eq!(
+        try_load_key_pair::<3>(&b).err(),
+        Some(DecodeError::PublicKeyHashMismatch),
+    );
+    b[hash_start] ^= 1;
+    let (_, decoded) = try_load_key_pair::<3>(&b).unwrap();
+    assert_eq!(decoded.hash(), pk.hash());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+    let (_, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, _) = encapsulate([3; 32], &pk);
+
+    let json = serde_json::to_string(&pk).unwrap();
+    assert_eq!(
+        json,
+        format!(
+            "\"{}\"",
+            hex::encode(pk.to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>())
+        ),
+    );
+    assert!(pk == serde_json::from_str(&json).unwrap());
+    let bin = bincode::serialize(&pk).unwrap();
+    assert!(pk == bincode::deserialize(&bin).unwrap());
+
+    let json = serde_json::to_string(&ct).unwrap();
+    let decoded = serde_json::from_str::<CipherText<3>>(&json).unwrap();
+    assert_eq!(
+        decoded.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>(),
+        ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>(),
+    );
+    let bin = bincode::serialize(&ct).unwrap();
+    let decoded = bincode::deserialize::<CipherText<3>>(&bin).unwrap();
+    assert_eq!(
+        decoded.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>(),
+        ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>(),
+    );
+
+    assert!(serde_json::from_str::<PublicKey<3>>("\"00\"").is_err());
+    assert!(serde_json::from_str::<PublicKey<2>>(&json).is_err());
+}
+
+#[cfg(feature = "serde-secret")]
+#[test]
+fn serde_secret() {
+    let (sk, _) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+
+    let json = serde_json::to_string(&sk).unwrap();
+    assert!(sk == serde_json::from_str(&json).unwrap());
+    let bin = bincode::serialize(&sk).unwrap();
+    assert!(sk == bincode::deserialize(&bin).unwrap());
+}
+
+// the round 3 cipher text is pinned, the noise parameters must not change it
+#[test]
+fn cipher_text_stable() {
+    fn check<const DIM: usize>(expected: &str)
+    where
+        Dim<DIM>: Config<32>,
+    {
+        let (_, pk) = key_pair::<DIM>(KeySeed {
+            main: [1; 32],
+            reject: [2; 32],
+        });
+        let (ct, _) = encapsulate([3; 32], &pk);
+        let mut sha = Sha3_256::default();
+        ct.to_bytes(&mut sha);
+        assert_eq!(expected, hex::encode(sha.finalize_fixed()));
+    }
+
+    check::<2>("790850fc86cd0aafe2341372875dce7b4d718c4337e70af17a859300f1677c44");
+    check::<3>("c39944283c4299413949edf367bc923272ec983899093c1dd2b3d79f8c0946f1");
+    check::<4>("a7e8f7c461c1c544aa6a78ea6b8038b58dea42277777334b169dad7b46e3bdf6");
+}
+
+#[test]
+fn debug() {
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, _) = encapsulate([3; 32], &pk);
+
+    assert_eq!(format!("{sk:?}"), "SecretKey<3>(<redacted>)");
+    assert_eq!(
+        format!("{pk:?}"),
+        format!(
+            "PublicKey<3>({})",
+            hex::encode(pk.to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>()),
+        ),
+    );
+    assert_eq!(
+        format!("{ct:?}"),
+        format!(
+            "CipherText<3>({})",
+            hex::encode(ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>()),
+        ),
+    );
+}
+
+#[test]
+fn display() {
+    let (_, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, _) = encapsulate([3; 32], &pk);
+
+    assert_eq!(
+        format!("{pk}"),
+        hex::encode(pk.to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>()),
+    );
+    assert_eq!(
+        format!("{ct}"),
+        hex::encode(ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>()),
+    );
+}
+
+#[test]
+fn clone_cipher_text() {
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, ss) = encapsulate([3; 32], &pk);
+    let cloned = ct.clone();
+    assert_eq!(ss, decapsulate(&sk, &pk, &cloned));
+    assert_eq!(
+        cloned.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>(),
+        ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>(),
+    );
+}
+
+#[test]
+fn zeroize_cipher_text() {
+    use subtle::ConstantTimeEq;
+    use zeroize::Zeroize;
+
+    use super::{indcpa, poly::Message, symmetric::Shake};
+
+    let (_, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (mut ct, _) = encapsulate([3; 32], &pk);
+    ct.zeroize();
+    assert_eq!(
+        ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>(),
+        [0; Kyber768::CIPHER_TEXT_BYTES],
+    );
+
+    // comparison is of the compressed coefficients, as before
+    let (_, pk) = indcpa::key_pair::<3, 32, Dim<3>, Shake>(&[1; 32]);
+    let ct = indcpa::encapsulate::<3, 32, Dim<3>, Shake>(&[3; 32], &Message([4; 32]), &pk);
+    let mut v = UpdateVec(vec![]);
+    ct.to_bytes(&mut v);
+    let decoded = indcpa::CipherText::<3, 32>::try_from_bytes(&v.0).unwrap();
+    assert!(bool::from(decoded.ct_eq(&ct)));
+
+    let mut zeroized = ct.clone();
+    zeroized.zeroize();
+    let zero = indcpa::CipherText::<3, 32>::try_from_bytes(&[0; Kyber768::CIPHER_TEXT_BYTES]);
+    assert!(bool::from(zeroized.ct_eq(&zero.unwrap())));
+    assert!(!bool::from(zeroized.ct_eq(&ct)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_update() {
+    use std::io;
+
+    use super::kem::WriteUpdate;
+
+    struct Limited(usize);
+
+    impl io::Write for Limited {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.0 < buf.len() {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            self.0 -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+
+    let mut writer = WriteUpdate::new(vec![]);
+    pk.to_bytes(&mut writer);
+    let bytes = writer.finish().unwrap();
+    assert_eq!(bytes, pk.to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>());
+
+    let mut writer = WriteUpdate::new(io::Cursor::new(vec![]));
+    sk.to_bytes(&pk, &mut writer);
+    let bytes = writer.finish().unwrap().into_inner();
+    assert_eq!(bytes, sk.to_array::<{ Kyber768::SECRET_KEY_BYTES }>(&pk));
+
+    let mut writer = WriteUpdate::new(Limited(100));
+    pk.to_bytes(&mut writer);
+    assert_eq!(
+        writer.finish().err().map(|err| err.kind()),
+        Some(io::ErrorKind::WriteZero),
+    );
+}
+
+#[cfg(feature = "hybrid")]
+#[test]
+fn hybrid() {
+    use sha3::{
+        Shake256,
+        digest::{ExtendableOutput, XofReader},
+    };
+    use x25519_dalek::StaticSecret;
+
+    use super::kem::hybrid::{self, HybridPublicKey, HybridCipherText};
+
+    let seed = || KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    };
+    let (sk, pk) = hybrid::key_pair::<3>(seed(), [4; 32]);
+    let pk_bytes = pk.to_array::<{ HybridPublicKey::<3>::BYTES }>();
+    let pk = HybridPublicKey::<3>::try_from_bytes(&pk_bytes).unwrap();
+
+    let (ct, ss) = hybrid::encapsulate([3; 32], [5; 32], &pk);
+    let ct_bytes = ct.to_array::<{ HybridCipherText::<3>::BYTES }>();
+    let ct = HybridCipherText::<3>::try_from_bytes(&ct_bytes).unwrap();
+    assert_eq!(ss, hybrid::decapsulate(&sk, &pk, &ct));
+
+    // the documented construction, from the components
+    let (_, kyber_pk) = key_pair::<3>(seed());
+    let (kyber_ct, kyber_ss) = encapsulate([3; 32], &kyber_pk);
+    let x25519_ss = StaticSecret::from([5; 32])
+        .diffie_hellman(&x25519_dalek::PublicKey::from(&StaticSecret::from([4; 32])))
+        .to_bytes();
+    let x25519_ct = x25519_dalek::PublicKey::from(&StaticSecret::from([5; 32]));
+    let kyber_ct = kyber_ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>();
+    assert_eq!(ct_bytes[..Kyber768::CIPHER_TEXT_BYTES], kyber_ct);
+    assert_eq!(
+        ct_bytes[Kyber768::CIPHER_TEXT_BYTES..],
+        *x25519_ct.as_bytes()
+    );
+
+    let mut expected = [0; 32];
+    Shake256::default()
+        .chain(kyber_ss)
+        .chain(x25519_ss)
+        .chain(kyber_ct)
+        .chain(x25519_ct.as_bytes())
+        .finalize_xof()
+        .read(&mut expected);
+    assert_eq!(ss, expected);
+
+    // another ephemeral key gives another secret
+    let (_, other) = hybrid::encapsulate([3; 32], [6; 32], &pk);
+    assert_ne!(ss, other);
+}
+
+#[cfg(feature = "noise-analysis")]
+#[test]
+fn decapsulate_noisy() {
+    use super::{indcpa, poly::Message, symmetric::Shake};
+
+    let (sk, pk) = indcpa::key_pair::<3, 32, Dim<3>, Shake>(&[1; 32]);
+    for i in 0..16 {
+        let message = [i * 17; 32];
+        let ct = indcpa::encapsulate::<3, 32, Dim<3>, Shake>(&[i; 32], &Message(message), &pk);
+        let (decrypted, noise) = indcpa::decapsulate_noisy(&ct, &sk);
+        assert_eq!(decrypted.0, message);
+        assert!(noise < Coefficient::Q as u16 / 4);
+    }
+
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, _) = encapsulate([3; 32], &pk);
+    let (_, noise) = super::kem::decapsulate_noisy(&sk, &ct);
+    assert!(noise < Coefficient::Q as u16 / 4);
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn decapsulate_margin() {
+    use super::{indcpa, poly::Message, symmetric::Shake};
+
+    // the decision boundaries of `compress_1`
+    let q = Coefficient::Q as u16;
+    for (x, bit) in [(832, 0), (833, 1), (2496, 1), (2497, 0)] {
+        assert_eq!(Coefficient::unpack(x).compress_1(), bit);
+    }
+
+    let (sk, pk) = indcpa::key_pair::<3, 32, Dim<3>, Shake>(&[1; 32]);
+    for i in 0..16 {
+        let message = [i * 17; 32];
+        let ct = indcpa::encapsulate::<3, 32, Dim<3>, Shake>(&[i; 32], &Message(message), &pk);
+        let (decrypted, margin) = indcpa::decapsulate_margin(&ct, &sk);
+        assert_eq!(decrypted.0, message);
+        assert!((1..=q / 4 + 1).contains(&margin), "{margin}");
+
+        // the boundaries are `q / 4` from the multiples of `q / 2`, up to rounding
+        #[cfg(feature = "noise-analysis")]
+        {
+            let (_, noise) = indcpa::decapsulate_noisy(&ct, &sk);
+            assert!(
+                (q / 4..=q / 4 + 1).contains(&(margin + noise)),
+                "{margin} {noise}"
+            );
+        }
+    }
+
+    let (sk, pk) = Kyber768::key_pair(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, _) = encapsulate([3; 32], &pk);
+    let (_, margin) = super::kem::decapsulate_margin(&sk, &ct);
+    assert!((1..=q / 4 + 1).contains(&margin), "{margin}");
+}
+
+// Kyber768 and Kyber1024 are the same in round 2 and in round 3, the known answer tests of round 3
+// cover them, see `kat::round2_kat_2` for Kyber512; the Kyber512 checks here are self-consistency only
+#[cfg(feature = "round2")]
+#[test]
+fn round2() {
+    use super::kem::{round2, encapsulate_derand};
+
+    fn same<const DIM: usize>(seed: KeySeed)
+    where
+        Dim<DIM>: Config<32>,
+        super::config::DimR2<DIM>: Config<32>,
+    {
+        let (sk, pk) = round2::key_pair::<DIM>(seed);
+        let (ct, ss) = round2::encapsulate_derand([3; 32], &pk);
+        let (expected_ct, expected_ss) = encapsulate_derand([3; 32], &pk);
+        let mut v = UpdateVec(vec![]);
+        ct.to_bytes(&mut v);
+        assert!(v.0.iter().copied().eq(expected_ct.byte_iter()));
+        assert_eq!(ss, expected_ss);
+        assert_eq!(round2::decapsulate(&sk, &pk, &ct), ss);
+        assert_eq!(round2::CipherText::<DIM>::BYTES, CipherText::<DIM>::BYTES);
+    }
+
+    let seed = || KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    };
+
+    same::<3>(seed());
+    assert!(round2::key_pair::<3>(seed()) == key_pair::<3>(seed()));
+    same::<4>(seed());
+    assert!(round2::key_pair::<4>(seed()) == key_pair::<4>(seed()));
+
+    // Kyber512, `eta1 = 2` gives another key, `dv = 3` a shorter cipher text
+    assert_eq!(round2::CipherText::<2>::BYTES, 736);
+    let (sk, pk) = round2::key_pair::<2>(seed());
+    assert!(pk != key_pair::<2>(seed()).1);
+
+    let (ct, ss) = round2::encapsulate([3; 32], &pk);
+    assert_eq!(round2::decapsulate(&sk, &pk, &ct), ss);
+    let bytes = ct.to_array::<736>();
+    let decoded = round2::CipherText::<2>::from_bytes(&bytes);
+    assert!(decoded == ct);
+    assert_eq!(round2::decapsulate(&sk, &pk, &decoded), ss);
+    assert!(CipherText::<2>::try_from_bytes(&bytes).is_err());
+
+    // the lowest bit of a 3 bit coefficient of `v`
+    let mut bytes = bytes;
+    bytes[640 + 94] ^= 0x80;
+    let tampered = round2::CipherText::<2>::from_bytes(&bytes);
+    assert!(tampered != ct);
+    assert_ne!(round2::decapsulate(&sk, &pk, &tampered), ss);
+}
+
+// the seeds are the same as in `cipher_text_stable`, expected values are hashes of the public key
+// and the cipher text, the shared secret and the implicit rejection of zero cipher text;
+// the values are the output of this implementation, they catch regressions only, the reference
+// is checked by `kat::kyber90s_kat_*`
+#[cfg(feature = "kyber90s")]
+#[test]
+fn kyber90s() {
+    use super::kem::kyber90s;
+
+    fn check<const DIM: usize>(expected: [&str; 4])
+    where
+        Dim<DIM>: Config<32>,
+    {
+        let (sk, pk) = kyber90s::key_pair::<DIM>(KeySeed {
+            main: [1; 32],
+            reject: [2; 32],
+        });
+        let mut sha = Sha3_256::default();
+        pk.to_bytes(&mut sha);
+        assert_eq!(expected[0], hex::encode(sha.finalize_fixed()));
+
+        let (ct, ss) = kyber90s::encapsulate([3; 32], &pk);
+        let mut sha = Sha3_256::default();
+        ct.to_bytes(&mut sha);
+        assert_eq!(expected[1], hex::encode(sha.finalize_fixed()));
+        assert_eq!(expected[2], hex::encode(ss));
+        assert_eq!(ss, kyber90s::decapsulate(&sk, &pk, &ct));
+
+        let mut v = UpdateVec(vec![]);
+        sk.to_bytes(&pk, &mut v);
+        let (sk, pk) = kyber90s::load_key_pair::<DIM>(&v.0);
+        assert_eq!(ss, kyber90s::decapsulate(&sk, &pk, &ct));
+
+        let ct = CipherText::<DIM>::from_bytes(&vec![0; CipherText::<DIM>::BYTES]);
+        assert_eq!(
+            expected[3],
+            hex::encode(kyber90s::decapsulate(&sk, &pk, &ct))
+        );
+    }
+
+    check::<2>([
+        "e51f2483451994af3cf6f396b8194b28f1efd68c4dab06aff0d63e2a747ff175",
+        "e09c3f54fd62b10423eacae5c97e8a063c4a197cd0625baed3baf84388ba6eb2",
+        "bd00a2006ee68727c1a0b0cd576c796d278de7810ba97f4761d7e4479992fc7d",
+        "e1301a70c89adb1e07178db814673dd57dc07715a8f7def874b2ba5c6b85dfc0",
+    ]);
+    check::<3>([
+        "43391ce1fa758dd7cf75816fd04bf0e4d7cb1c6eebf6bcf5ed2e24b841b9427d",
+        "b1d7e213b29033dc990af7b17654338d797e309b206065d05b360e748564c812",
+        "03c57329823767b2b22d585c852353deb24f653f3176983bf7344f3324abd0c7",
+        "e513a77c001d5e32859838665c33d4f2d35cbf31fde55836b8308af84edbe4ae",
+    ]);
+    check::<4>([
+        "4fb11f60f6486d4d24f291b930197b30b04ea1c82a10646137c86a1ad49f8652",
+        "2c38984260e3d7a4ebb85e9ad0e17787a18787e2849ea0d160647d97bafbc834",
+        "57eae8afc8a78ce82063857f09ca31f046baa3e5a3765e1e92eba8c395393a87",
+        "75e536f0c4327ba67d5ea6d242d055a33dbb0e748f8ccb085c63bcab1c5bdfcd",
+    ]);
+}
+
+#[cfg(feature = "getrandom")]
+#[test]
+fn os_entropy() {
+    use super::kem::{key_pair_os, encapsulate_os};
+
+    let (sk, pk) = key_pair_os::<3>();
+    let (ct, ss) = encapsulate_os(&pk);
+    assert_eq!(ss, decapsulate(&sk, &pk, &ct));
+
+    let (_, other_pk) = key_pair_os::<3>();
+    assert!(pk != other_pk);
+}
+
+#[cfg(feature = "unstable-cpa")]
+#[test]
+fn cpa() {
+    use sha3::Sha3_512;
+    use subtle::ConstantTimeEq;
+
+    use super::kem::{cpa, encapsulate_derand};
+
+    let (sk, pk) = cpa::key_pair::<3>(&[1; 32]);
+    // the key pair of the KEM is built on the same seed
+    let (_, kem_pk) = key_pair::<3>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let mut v = UpdateVec(vec![]);
+    pk.to_bytes(&mut v);
+    assert_eq!(v.0, kem_pk.to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>());
+    let pk = cpa::PublicKey::<3>::try_from(v.0.as_slice()).unwrap();
+
+    let ct = cpa::encrypt(&pk, &[3; 32], &[4; 32]);
+    let mut v = UpdateVec(vec![]);
+    ct.to_bytes(&mut v);
+    assert_eq!(v.0.len(), Kyber768::CIPHER_TEXT_BYTES);
+    let ct = cpa::CipherText::<3>::try_from(v.0.as_slice()).unwrap();
+    assert_eq!(cpa::decrypt(&sk, &ct), [3; 32]);
+
+    // deterministic, the same coins give the same cipher text
+    assert!(bool::from(ct.ct_eq(&cpa::encrypt(&pk, &[3; 32], &[4; 32]))));
+    assert!(!bool::from(
+        ct.ct_eq(&cpa::encrypt(&pk, &[3; 32], &[5; 32]))
+    ));
+
+    // the cipher text of the KEM is the encryption of `m` with the coins `G(m || H(pk))`
+    let pk_hash = Sha3_256::default()
+        .chain(kem_pk.to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>())
+        .finalize_fixed();
+    let g = Sha3_512::default()
+        .chain([3; 32])
+        .chain(pk_hash)
+        .finalize_fixed();
+    let coins = g[32..].try_into().unwrap();
+    let (kem_ct, _) = encapsulate_derand([3; 32], &kem_pk);
+    let mut v = UpdateVec(vec![]);
+    cpa::encrypt(&pk, &[3; 32], &coins).to_bytes(&mut v);
+    assert_eq!(v.0, kem_ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>());
+
+    // wrapping the key computes the same hash as the key generation of the KEM
+    assert!(bool::from(kem_pk.inner().ct_eq(&pk)));
+    let wrapped = PublicKey::from(pk);
+    assert!(wrapped == kem_pk);
+    assert!(bool::from(wrapped.into_inner().ct_eq(kem_pk.inner())));
+}
+
+#[cfg(feature = "unstable-cpa")]
+#[test]
+fn cpa_matrix() {
+    use super::{kem::cpa, poly::Poly, symmetric::Shake};
+
+    let seed = [7; 32];
+    // the reference samples `A[i][j]` from `XOF(seed, j, i)`
+    let expected = |transposed: bool| {
+        let mut v = UpdateVec(vec![]);
+        for i in 0..3 {
+            for j in 0..3 {
+                let (x, y) = if transposed { (i, j) } else { (j, i) };
+                Poly::<32, false>::get_uniform::<Shake>(&seed, x, y).to_bytes(&mut v);
+            }
+        }
+        v.0
+    };
+    for transposed in [false, true] {
+        let mut v = UpdateVec(vec![]);
+        cpa::matrix::<3, _>(&seed, transposed, &mut v);
+        assert_eq!(v.0.len(), 9 * 384);
+        assert_eq!(v.0, expected(transposed));
+    }
+}
+
+// the success path only, the errors are `JsError`, it works on `wasm32` only, see `tests/wasm.rs`
+#[cfg(feature = "wasm")]
+#[test]
+fn wasm() {
+    use super::wasm::{generate, public_key, encapsulate};
+
+    let key_pair = generate();
+    let (sk, pk) = load_key_pair::<3>(&key_pair);
+    assert_eq!(
+        public_key(&key_pair).unwrap(),
+        pk.to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>(),
+    );
+
+    let encapsulated = encapsulate(&public_key(&key_pair).unwrap()).unwrap();
+    let ct = CipherText::<3>::from_bytes(&encapsulated.cipher_text());
+    assert_eq!(encapsulated.shared_secret(), decapsulate(&sk, &pk, &ct));
+    assert_eq!(
+        super::wasm::decapsulate(&key_pair, &encapsulated.cipher_text()).unwrap(),
+        encapsulated.shared_secret(),
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn std_error() {
+    use std::{boxed::Box, error::Error};
+
+    fn parse(b: &[u8]) -> Result<PublicKey<3>, Box<dyn Error>> {
+        Ok(PublicKey::try_from_bytes(b)?)
+    }
+
+    let err = parse(&[0; 100]).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        format!("unexpected length 100, expected {}", PublicKey::<3>::BYTES),
+    );
+    assert!(err.downcast_ref::<DecodeError>().is_some());
+}
+
+#[test]
+fn key_pair_checked() {
+    use super::kem::key_pair_checked;
+
+    let seed = || KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    };
+    let (sk, pk) = key_pair_checked::<3>(seed()).unwrap();
+    let (expected_sk, expected_pk) = key_pair::<3>(seed());
+    assert!(sk == expected_sk);
+    assert!(pk == expected_pk);
+}
+
+#[test]
+fn fingerprint() {
+    let (_, pk) = key_pair::<3>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let mut v = UpdateVec(b"vru-kyber-pk-fp".to_vec());
+    pk.to_bytes(&mut v);
+    let expected: [u8; 32] = Sha3_256::default().chain(&v.0).finalize_fixed().into();
+    assert_eq!(pk.fingerprint(), expected);
+    assert_ne!(pk.fingerprint(), pk.hash());
+}
+
+#[test]
+fn cipher_text_order() {
+    use std::collections::{BTreeSet, HashSet};
+
+    let (_, pk) = key_pair::<2>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let cts = (0..16)
+        .map(|i| encapsulate([i % 8; 32], &pk).0)
+        .collect::<Vec<_>>();
+
+    let mut bytes = cts
+        .iter()
+        .map(|ct| ct.to_array::<{ CipherText::<2>::BYTES }>().to_vec())
+        .collect::<Vec<_>>();
+    bytes.sort();
+    bytes.dedup();
+    assert_eq!(bytes.len(), 8);
+
+    let ordered = cts.iter().cloned().collect::<BTreeSet<_>>();
+    let ordered = ordered
+        .iter()
+        .map(|ct| ct.to_array::<{ CipherText::<2>::BYTES }>().to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(ordered, bytes);
+
+    assert_eq!(cts.into_iter().collect::<HashSet<_>>().len(), 8);
+}
+
+#[test]
+fn public_key_hash() {
+    use std::collections::HashMap;
+
+    let pk = |main| {
+        key_pair::<3>(KeySeed {
+            main,
+            reject: [0; 32],
+        })
+        .1
+    };
+    let mut peers = HashMap::new();
+    peers.insert(pk([1; 32]), 1);
+    peers.insert(pk([2; 32]), 2);
+    // the same key decoded from the bytes is the same entry
+    let decoded = PublicKey::<3>::from_bytes(&pk([1; 32]).to_array::<{ PublicKey::<3>::BYTES }>());
+    peers.insert(decoded, 3);
+    assert_eq!(peers.len(), 2);
+    assert_eq!(peers[&pk([1; 32])], 3);
+    assert_eq!(peers[&pk([2; 32])], 2);
+}
+
+#[test]
+fn borrowed() {
+    use super::kem::{PublicKeyRef, CipherTextRef};
+
+    let (_, pk) = key_pair::<3>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let mut bytes = pk.to_array::<{ PublicKey::<3>::BYTES }>();
+    let pk_ref = PublicKeyRef::<3>::try_from_bytes(&bytes).unwrap();
+    assert_eq!(pk_ref.validate(), Ok(()));
+    assert_eq!(pk_ref.seed(), &bytes[(PublicKey::<3>::BYTES - 32)..]);
+    assert!(pk_ref.decode().unwrap() == pk);
+    assert_eq!(
+        PublicKeyRef::<3>::try_from_bytes(&bytes[1..]),
+        Err(DecodeError::UnexpectedLength {
+            expected: PublicKey::<3>::BYTES,
+            got: PublicKey::<3>::BYTES - 1,
+        }),
+    );
+
+    // the second coefficient of the last triple is `q`
+    let i = PublicKey::<3>::BYTES - 32 - 3;
+    bytes[i + 1] = (bytes[i + 1] & 0x0f) | 0x10;
+    bytes[i + 2] = 0xd0;
+    let pk_ref = PublicKeyRef::<3>::try_from_bytes(&bytes).unwrap();
+    assert_eq!(pk_ref.validate(), Err(DecodeError::NonCanonicalCoefficient));
+    assert_eq!(
+        pk_ref.decode().unwrap_err(),
+        DecodeError::NonCanonicalCoefficient
+    );
+
+    let (ct, _) = encapsulate([3; 32], &pk);
+    let bytes = ct.to_array::<{ CipherText::<3>::BYTES }>();
+    let ct_ref = CipherTextRef::<3>::try_from(&bytes[..]).unwrap();
+    assert!(ct_ref.decode() == ct);
+    assert!(CipherTextRef::<3>::try_from_bytes(&bytes[1..]).is_err());
+}
+
+#[test]
+fn encapsulate_from_xof() {
+    use sha3::{Shake256, digest::ExtendableOutput};
+
+    use super::kem::encapsulate_from_xof;
+
+    let (sk, pk) = key_pair::<3>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let transcript = Shake256::default().chain(b"transcript");
+    let mut reader = transcript.clone().finalize_xof();
+    let (ct, ss) = encapsulate_from_xof(&mut reader, &pk);
+    let (next_ct, _) = encapsulate_from_xof(&mut reader, &pk);
+
+    let mut seed = [0; 64];
+    transcript.finalize_xof_into(&mut seed);
+    let (expected_ct, expected_ss) = encapsulate(seed[..32].try_into().unwrap(), &pk);
+    assert!(ct == expected_ct);
+    assert_eq!(ss, expected_ss);
+    assert!(next_ct == encapsulate(seed[32..].try_into().unwrap(), &pk).0);
+    assert_eq!(decapsulate(&sk, &pk, &ct), ss);
+}
+
+#[test]
+fn context() {
+    use super::kem::{encapsulate_with_context, decapsulate_with_context};
+
+    let (sk, pk) = key_pair::<3>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, ss) = encapsulate_with_context([3; 32], &pk, b"deployment a");
+    assert_eq!(decapsulate_with_context(&sk, &pk, &ct, b"deployment a"), ss);
+    assert_ne!(decapsulate_with_context(&sk, &pk, &ct, b"deployment b"), ss);
+
+    // the cipher text does not depend on the context, the empty context is the standard one
+    let (expected_ct, expected_ss) = encapsulate([3; 32], &pk);
+    assert!(ct == expected_ct);
+    assert_ne!(ss, expected_ss);
+    assert_eq!(encapsulate_with_context([3; 32], &pk, &[]).1, expected_ss);
+    assert_eq!(decapsulate_with_context(&sk, &pk, &ct, &[]), expected_ss);
+}
+
+#[test]
+fn long_shared_secret() {
+    use super::kem::{encapsulate_ss, decapsulate_ss};
+
+    let (sk, pk) = key_pair::<3>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, ss) = encapsulate_ss::<3, 64>([3; 32], &pk);
+    assert_eq!(decapsulate_ss::<3, 64>(&sk, &pk, &ct), ss);
+
+    // the same state squeezed further, the prefix is the standard secret
+    let (expected_ct, expected_ss) = encapsulate([3; 32], &pk);
+    assert!(ct == expected_ct);
+    assert_eq!(ss[..32], expected_ss);
+
+    // the rejected secret is extended the same way
+    let ct = CipherText::<3>::from_bytes(&[0; 1088]);
+    assert_eq!(
+        decapsulate_ss::<3, 64>(&sk, &pk, &ct)[..32],
+        decapsulate(&sk, &pk, &ct)
+    );
+}
+
+#[test]
+fn weak_seed() {
+    use subtle::ConstantTimeEq;
+
+    use super::kem::{try_key_pair, WeakSeed};
+
+    let seed = |main, reject| KeySeed { main, reject };
+    assert!(matches!(
+        try_key_pair::<3>(seed([0; 32], [2; 32])),
+        Err(WeakSeed)
+    ));
+    assert!(matches!(
+        try_key_pair::<3>(seed([1; 32], [0; 32])),
+        Err(WeakSeed)
+    ));
+
+    // a structured seed is not rejected, only the literal zero
+    let mut main = [0; 32];
+    main[31] = 1;
+    let (sk, pk) = try_key_pair::<3>(seed(main, [2; 32])).unwrap();
+    let (expected_sk, expected_pk) = key_pair::<3>(seed(main, [2; 32]));
+    assert!(bool::from(sk.ct_eq(&expected_sk) & pk.ct_eq(&expected_pk)));
+}
+
+fn byte_iter_dim<const DIM: usize>()
+where
+    Dim<DIM>: Config<32>,
+{
+    let (_, pk) = key_pair::<DIM>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (ct, _) = encapsulate([3; 32], &pk);
+
+    let mut v = UpdateVec(vec![]);
+    pk.to_bytes(&mut v);
+    assert_eq!(pk.byte_iter().collect::<Vec<_>>(), v.0);
+
+    let mut v = UpdateVec(vec![]);
+    ct.to_bytes(&mut v);
+    assert_eq!(ct.byte_iter().collect::<Vec<_>>(), v.0);
+}
+
+#[test]
+fn byte_iter() {
+    byte_iter_dim::<2>();
+    byte_iter_dim::<3>();
+    byte_iter_dim::<4>();
+}
+
+#[test]
+fn decapsulate_many() {
+    use super::kem::decapsulate_many;
+
+    let (sk, pk) = key_pair::<3>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let (cts, expected): (Vec<_>, Vec<_>) = (0..8).map(|i| encapsulate([i; 32], &pk)).unzip();
+    let mut shared_secrets = [[0; 32]; 8];
+    decapsulate_many(&sk, &pk, &cts, &mut shared_secrets);
+    assert_eq!(shared_secrets.to_vec(), expected);
+}
+
+#[test]
+fn key_pair_bundle() {
+    use super::kem::KeyPair;
+
+    let seed = || KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    };
+    let pair = KeyPair::<3>::from_seed(seed());
+    let (sk, pk) = key_pair::<3>(seed());
+    assert!(pair == KeyPair::from((sk.clone(), pk.clone())));
+    assert!(pair == KeyPair::from_secret_key(sk.clone(), &[1; 32]));
+
+    let (ct, ss) = encapsulate([3; 32], pair.public_key());
+    assert_eq!(pair.decapsulate(&ct), ss);
+    assert_eq!(decapsulate(&sk, &pk, &ct), ss);
+
+    let mut v = UpdateVec(vec![]);
+    pair.to_bytes(&mut v);
+    assert_eq!(v.0.len(), KeyPair::<3>::BYTES);
+    assert!(KeyPair::<3>::try_from_bytes(&v.0).unwrap() == pair);
+
+    let (sk_back, pk_back) = pair.into_parts();
+    assert!(sk_back == sk);
+    assert!(pk_back == pk);
+}
+
+#[test]
+fn encapsulate_batch() {
+    use sha3::{Shake256, digest::ExtendableOutput};
+
+    use super::kem::encapsulate_batch;
+
+    let pairs = (0..4)
+        .map(|i| {
+            key_pair::<3>(KeySeed {
+                main: [i; 32],
+                reject: [i + 16; 32],
+            })
+        })
+        .collect::<Vec<_>>();
+    let pks = pairs.iter().map(|(_, pk)| pk).collect::<Vec<_>>();
+
+    let mut seeds = [0; 32 * 4];
+    Shake256::default()
+        .chain([5; 32])
+        .finalize_xof_into(&mut seeds);
+
+    let results = encapsulate_batch([5; 32], &pks).collect::<Vec<_>>();
+    assert_eq!(results.len(), 4);
+    for (((ct, ss), (sk, pk)), seed) in results.iter().zip(&pairs).zip(seeds.chunks(32)) {
+        let (expected_ct, expected_ss) = encapsulate(seed.try_into().unwrap(), pk);
+        assert!(*ct == expected_ct);
+        assert_eq!(*ss, expected_ss);
+        assert_eq!(decapsulate(sk, pk, ct), *ss);
+    }
+    // independent secrets, even to the same key twice
+    let results = encapsulate_batch([5; 32], &[pks[0], pks[0]]).collect::<Vec<_>>();
+    assert_ne!(results[0].1, results[1].1);
+}
+
+#[test]
+fn secret_key_zeroize_on_drop() {
+    use core::{
+        mem::{self, MaybeUninit},
+        ptr,
+    };
+
+    // drop the key in place and read the memory it occupied, the key has no padding,
+    // it is the `i16` coefficients followed by the 32 bytes of `reject`
+    fn dropped_bytes<T>(value: T) -> Vec<u8> {
+        let mut slot = MaybeUninit::new(value);
+        let p = slot.as_mut_ptr();
+        let before = (0..mem::size_of::<T>())
+            .map(|i| unsafe { ptr::read_volatile(p.cast::<u8>().add(i)) })
+            .collect::<Vec<_>>();
+        assert!(before.iter().any(|b| *b != 0));
+        unsafe { ptr::drop_in_place(p) };
+        (0..mem::size_of::<T>())
+            .map(|i| unsafe { ptr::read_volatile(p.cast::<u8>().add(i)) })
+            .collect()
+    }
+
+    let seed = || KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    };
+    assert!(dropped_bytes(key_pair::<2>(seed()).0)
+        .iter()
+        .all(|b| *b == 0));
+    assert!(dropped_bytes(key_pair::<3>(seed()).0)
+        .iter()
+        .all(|b| *b == 0));
+    assert!(dropped_bytes(key_pair::<4>(seed()).0)
+        .iter()
+        .all(|b| *b == 0));
+
+    // the bare key of the public key encryption as well, it is not wrapped into the kem key
+    let (sk, _) = super::indcpa::key_pair::<3, 32, Dim<3>, super::symmetric::Shake>(&[1; 32]);
+    assert!(dropped_bytes(sk).iter().all(|b| *b == 0));
+}
+
+// the stages compressed with the parameters of Kyber768 are the cipher text
+#[cfg(feature = "bench")]
+#[test]
+fn encapsulate_stages() {
+    use super::{bench, kem::encapsulate_derand, poly::Poly};
+
+    let (_, pk) = key_pair::<3>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let stages = bench::encapsulate_stages([3; 32], &pk);
+    assert_eq!(
+        (stages.cipher_text.clone(), stages.shared_secret),
+        encapsulate_derand([3; 32], &pk)
+    );
+
+    let poly = |c: [u16; 256]| {
+        let mut p = Poly::<32, true>::zero();
+        for (i, c) in c.iter().enumerate() {
+            p[i] = Coefficient(*c as i16);
+        }
+        p
+    };
+    let mut v = UpdateVec(vec![]);
+    for b in &stages.b {
+        <Dim<3> as Config<32>>::compress_vec(&poly(b.coefficients()), &mut v);
+    }
+    <Dim<3> as Config<32>>::compress(&poly(stages.v.coefficients()), &mut v);
+    assert!(v.0.iter().copied().eq(stages.cipher_text.byte_iter()));
+}
+
+#[test]
+fn key_pair_from_slice() {
+    use super::kem::key_pair_from_slice;
+
+    let mut buffer = [0; 80];
+    buffer
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, b)| *b = i as u8);
+    let seed = &buffer[8..72];
+
+    let (sk, pk) = key_pair_from_slice::<3>(seed).unwrap();
+    let expected = key_pair::<3>(KeySeed::from_bytes(seed.try_into().unwrap()));
+    assert!((sk, pk) == expected);
+
+    let main = buffer[8..40].try_into().unwrap();
+    let reject = buffer[40..72].try_into().unwrap();
+    assert!(key_pair_from_slice::<3>(seed).unwrap() == key_pair::<3>(KeySeed { main, reject }));
+
+    for len in [0, 32, 63, 65] {
+        assert_eq!(
+            key_pair_from_slice::<3>(&buffer[..len]).err(),
+            Some(DecodeError::UnexpectedLength {
+                expected: KeySeed::BYTES,
+                got: len,
+            })
+        );
     }
 }
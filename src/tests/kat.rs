@@ -0,0 +1,332 @@
+//! The known answer tests of the reference implementation, the `PQCkemKAT_*.rsp` files
+//! produced by `PQCgenKAT_kem`, see <https://github.com/pq-crystals/kyber>.
+
+use std::{
+    string::String,
+    fs::File,
+    io::{BufReader, BufRead},
+    vec::Vec,
+};
+
+use aes::{
+    Aes256,
+    cipher::{KeyInit, BlockEncrypt},
+};
+
+use super::{
+    UpdateVec,
+    super::{
+        config::{Dim, Config},
+        kem::{KeySeed, SecretKey, PublicKey, key_pair, encapsulate, decapsulate},
+    },
+};
+#[cfg(feature = "kyber90s")]
+use super::super::kem::kyber90s;
+#[cfg(feature = "round2")]
+use super::super::{config::DimR2, kem::round2};
+
+/// The deterministic random bit generator of the KAT harness, `AES256_CTR_DRBG` from `rng.c`.
+struct Drbg {
+    key: [u8; 32],
+    v: [u8; 16],
+}
+
+impl Drbg {
+    /// `randombytes_init` without the personalization string.
+    fn new(entropy: &[u8; 48]) -> Self {
+        let mut drbg = Drbg {
+            key: [0; 32],
+            v: [0; 16],
+        };
+        drbg.update(Some(entropy));
+        drbg
+    }
+
+    fn increment(&mut self) {
+        for b in self.v.iter_mut().rev() {
+            let (x, overflow) = b.overflowing_add(1);
+            *b = x;
+            if !overflow {
+                break;
+            }
+        }
+    }
+
+    fn block(&mut self) -> [u8; 16] {
+        self.increment();
+        let mut block = self.v.into();
+        Aes256::new(&self.key.into()).encrypt_block(&mut block);
+        block.into()
+    }
+
+    fn update(&mut self, provided: Option<&[u8; 48]>) {
+        let mut temp = [0; 48];
+        for chunk in temp.chunks_mut(16) {
+            chunk.clone_from_slice(&self.block());
+        }
+        if let Some(provided) = provided {
+            for (t, p) in temp.iter_mut().zip(provided) {
+                *t ^= p;
+            }
+        }
+        self.key.clone_from_slice(&temp[..32]);
+        self.v.clone_from_slice(&temp[32..]);
+    }
+
+    /// `randombytes`
+    fn fill(&mut self, x: &mut [u8]) {
+        for chunk in x.chunks_mut(16) {
+            let block = self.block();
+            chunk.clone_from_slice(&block[..chunk.len()]);
+        }
+        self.update(None);
+    }
+
+    fn gen<const N: usize>(&mut self) -> [u8; N] {
+        let mut x = [0; N];
+        self.fill(&mut x);
+        x
+    }
+}
+
+#[derive(Default)]
+struct Record {
+    count: String,
+    seed: String,
+    pk: String,
+    sk: String,
+    ct: String,
+    ss: String,
+}
+
+/// The serialized public key and key pair, the variants have their own key types.
+trait KeyPair {
+    fn to_bytes(&self) -> (Vec<u8>, Vec<u8>);
+}
+
+impl<const DIM: usize> KeyPair for (SecretKey<DIM>, PublicKey<DIM>) {
+    fn to_bytes(&self) -> (Vec<u8>, Vec<u8>) {
+        let (sk, pk) = self;
+        let (mut p, mut s) = (UpdateVec(vec![]), UpdateVec(vec![]));
+        pk.to_bytes(&mut p);
+        sk.to_bytes(pk, &mut s);
+        (p.0, s.0)
+    }
+}
+
+#[cfg(feature = "kyber90s")]
+impl<const DIM: usize> KeyPair for (kyber90s::SecretKey<DIM>, kyber90s::PublicKey<DIM>) {
+    fn to_bytes(&self) -> (Vec<u8>, Vec<u8>) {
+        let (sk, pk) = self;
+        let (mut p, mut s) = (UpdateVec(vec![]), UpdateVec(vec![]));
+        pk.to_bytes(&mut p);
+        sk.to_bytes(pk, &mut s);
+        (p.0, s.0)
+    }
+}
+
+impl Record {
+    // the files are in upper case hex; the key pair takes `d` and `z`, the encapsulation
+    // takes the next 32 bytes, in the order `crypto_kem_keypair` and `crypto_kem_enc` call
+    // `randombytes`
+    fn check<const DIM: usize>(&self)
+    where
+        Dim<DIM>: Config<32>,
+    {
+        self.check_with(key_pair::<DIM>, |seed, (sk, pk)| {
+            let (ct, ss) = encapsulate(seed, pk);
+            let mut v = UpdateVec(vec![]);
+            ct.to_bytes(&mut v);
+            (v.0, ss, decapsulate(sk, pk, &ct))
+        });
+    }
+
+    // the variant encapsulates and gives the serialized cipher text, the shared secret
+    // and the decapsulated shared secret
+    fn check_with<P, K, E>(&self, key_pair: K, encapsulate: E)
+    where
+        P: KeyPair,
+        K: Fn(KeySeed) -> P,
+        E: Fn([u8; 32], &P) -> (Vec<u8>, [u8; 32], [u8; 32]),
+    {
+        let entropy = hex::decode(&self.seed).unwrap().try_into().unwrap();
+        let mut drbg = Drbg::new(&entropy);
+        let main = drbg.gen();
+        let reject = drbg.gen();
+        let keys = key_pair(KeySeed { main, reject });
+
+        let (pk, sk) = keys.to_bytes();
+        assert_eq!(self.pk, hex::encode_upper(pk), "{}", self.count);
+        assert_eq!(self.sk, hex::encode_upper(sk), "{}", self.count);
+
+        let (ct, ss, decapsulated) = encapsulate(drbg.gen(), &keys);
+        assert_eq!(self.ct, hex::encode_upper(ct), "{}", self.count);
+        assert_eq!(self.ss, hex::encode_upper(ss), "{}", self.count);
+        assert_eq!(self.ss, hex::encode_upper(decapsulated), "{}", self.count);
+    }
+}
+
+#[cfg(feature = "kyber90s")]
+impl Record {
+    fn check_kyber90s<const DIM: usize>(&self)
+    where
+        Dim<DIM>: Config<32>,
+    {
+        self.check_with(kyber90s::key_pair::<DIM>, |seed, (sk, pk)| {
+            let (ct, ss) = kyber90s::encapsulate(seed, pk);
+            let mut v = UpdateVec(vec![]);
+            ct.to_bytes(&mut v);
+            (v.0, ss, kyber90s::decapsulate(sk, pk, &ct))
+        });
+    }
+}
+
+#[cfg(feature = "round2")]
+impl Record {
+    fn check_round2<const DIM: usize>(&self)
+    where
+        DimR2<DIM>: Config<32>,
+    {
+        self.check_with(round2::key_pair::<DIM>, |seed, (sk, pk)| {
+            let (ct, ss) = round2::encapsulate(seed, pk);
+            let mut v = UpdateVec(vec![]);
+            ct.to_bytes(&mut v);
+            (v.0, ss, round2::decapsulate(sk, pk, &ct))
+        });
+    }
+}
+
+fn load_and_check<const DIM: usize>()
+where
+    Dim<DIM>: Config<32>,
+{
+    let rsp = format!("target/PQCkemKAT_{}.rsp", SecretKey::<DIM>::BYTES);
+    for record in load(&rsp) {
+        record.check::<DIM>();
+    }
+}
+
+fn load(rsp: &str) -> Vec<Record> {
+    let file = File::open(rsp).unwrap_or_else(|_| panic!("KAT file `{rsp}` is missing"));
+
+    let mut records = Vec::new();
+    let mut record = Record::default();
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let (name, value) = match line.split_once(" = ") {
+            Some((name, value)) => (name, value.trim().into()),
+            None => continue,
+        };
+        match name {
+            "count" => record.count = value,
+            "seed" => record.seed = value,
+            "pk" => record.pk = value,
+            "sk" => record.sk = value,
+            "ct" => record.ct = value,
+            "ss" => {
+                record.ss = value;
+                records.push(core::mem::take(&mut record));
+            }
+            _ => (),
+        }
+    }
+
+    assert!(!records.is_empty(), "KAT file `{rsp}` has no records");
+    records
+}
+
+#[test]
+#[ignore = "requires `target/PQCkemKAT_1632.rsp`"]
+fn kat_2() {
+    load_and_check::<2>();
+}
+
+#[test]
+#[ignore = "requires `target/PQCkemKAT_2400.rsp`"]
+fn kat_3() {
+    load_and_check::<3>();
+}
+
+#[test]
+#[ignore = "requires `target/PQCkemKAT_3168.rsp`"]
+fn kat_4() {
+    load_and_check::<4>();
+}
+
+// the files of the reference implementation built with `KYBER_90S`, under `target/kyber90s/`
+#[cfg(feature = "kyber90s")]
+fn load_and_check_kyber90s<const DIM: usize>()
+where
+    Dim<DIM>: Config<32>,
+{
+    let rsp = format!("target/kyber90s/PQCkemKAT_{}.rsp", SecretKey::<DIM>::BYTES);
+    for record in load(&rsp) {
+        record.check_kyber90s::<DIM>();
+    }
+}
+
+#[cfg(feature = "kyber90s")]
+#[test]
+#[ignore = "requires `target/kyber90s/PQCkemKAT_1632.rsp`"]
+fn kyber90s_kat_2() {
+    load_and_check_kyber90s::<2>();
+}
+
+#[cfg(feature = "kyber90s")]
+#[test]
+#[ignore = "requires `target/kyber90s/PQCkemKAT_2400.rsp`"]
+fn kyber90s_kat_3() {
+    load_and_check_kyber90s::<3>();
+}
+
+#[cfg(feature = "kyber90s")]
+#[test]
+#[ignore = "requires `target/kyber90s/PQCkemKAT_3168.rsp`"]
+fn kyber90s_kat_4() {
+    load_and_check_kyber90s::<4>();
+}
+
+// the files of the round 2 submission, the same harness, under `target/round2/`
+#[cfg(feature = "round2")]
+fn load_and_check_round2<const DIM: usize>()
+where
+    DimR2<DIM>: Config<32>,
+{
+    let rsp = format!("target/round2/PQCkemKAT_{}.rsp", SecretKey::<DIM>::BYTES);
+    for record in load(&rsp) {
+        record.check_round2::<DIM>();
+    }
+}
+
+#[cfg(feature = "round2")]
+#[test]
+#[ignore = "requires `target/round2/PQCkemKAT_1632.rsp`"]
+fn round2_kat_2() {
+    load_and_check_round2::<2>();
+}
+
+#[cfg(feature = "round2")]
+#[test]
+#[ignore = "requires `target/round2/PQCkemKAT_2400.rsp`"]
+fn round2_kat_3() {
+    load_and_check_round2::<3>();
+}
+
+#[cfg(feature = "round2")]
+#[test]
+#[ignore = "requires `target/round2/PQCkemKAT_3168.rsp`"]
+fn round2_kat_4() {
+    load_and_check_round2::<4>();
+}
+
+// the harness seeds the generator with `0, 1, ..., 47`, the first record has this seed
+#[test]
+fn drbg() {
+    let entropy = (0..48).collect::<Vec<u8>>().try_into().unwrap();
+    let mut drbg = Drbg::new(&entropy);
+    let seed = drbg.gen::<48>();
+    assert_eq!(
+        hex::encode_upper(seed),
+        "061550234D158C5EC95595FE04EF7A25767F2E24CC2BC479D09D86DC9ABCFDE7\
+         056A8C266F9EF97ED08541DBD2E1FFA1",
+    );
+}
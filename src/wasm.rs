@@ -0,0 +1,100 @@
+//! Kyber768 for the browser, the `wasm-bindgen` functions over the serialized bytes.
+//!
+//! The entropy comes from `crypto.getRandomValues`, the `js` backend of `getrandom`.
+//! The shared secrets are returned as fresh byte arrays, JavaScript cannot wipe them,
+//! the copies on the Rust side are wiped.
+
+use std::vec::Vec;
+
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroize;
+
+use super::kem::{Kyber768, KeyPair, PublicKey, CipherText, key_pair_os, encapsulate_os};
+
+const DIM: usize = 3;
+
+/// Generates the key pair, the secret key followed by the public key, the format
+/// of `kem::load_key_pair`. Keep it secret, see `publicKey`.
+#[wasm_bindgen]
+#[must_use]
+pub fn generate() -> Vec<u8> {
+    let (sk, pk) = key_pair_os::<DIM>();
+    let mut b = sk.to_array::<{ Kyber768::SECRET_KEY_BYTES }>(&pk);
+    let v = b.to_vec();
+    b.zeroize();
+    v
+}
+
+/// The public key of the key pair `generate` gives.
+///
+/// # Errors
+///
+/// will return an error if the key pair is malformed
+#[wasm_bindgen(js_name = publicKey)]
+pub fn public_key(key_pair: &[u8]) -> Result<Vec<u8>, JsError> {
+    let pair = KeyPair::<DIM>::try_from_bytes(key_pair)?;
+    Ok(pair
+        .public_key()
+        .to_array::<{ Kyber768::PUBLIC_KEY_BYTES }>()
+        .to_vec())
+}
+
+/// The cipher text and the shared secret, the getters return copies.
+#[wasm_bindgen]
+pub struct Encapsulated {
+    cipher_text: Vec<u8>,
+    shared_secret: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Encapsulated {
+    #[wasm_bindgen(getter, js_name = cipherText)]
+    #[must_use]
+    pub fn cipher_text(&self) -> Vec<u8> {
+        self.cipher_text.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = sharedSecret)]
+    #[must_use]
+    pub fn shared_secret(&self) -> Vec<u8> {
+        self.shared_secret.clone()
+    }
+}
+
+impl Drop for Encapsulated {
+    fn drop(&mut self) {
+        self.shared_secret.zeroize();
+    }
+}
+
+/// Encapsulates the fresh secret using the public key of receiver.
+///
+/// # Errors
+///
+/// will return an error if the public key is malformed
+#[wasm_bindgen]
+pub fn encapsulate(public_key: &[u8]) -> Result<Encapsulated, JsError> {
+    let pk = PublicKey::<DIM>::try_from_bytes(public_key)?;
+    let (ct, mut ss) = encapsulate_os(&pk);
+    let encapsulated = Encapsulated {
+        cipher_text: ct.to_array::<{ Kyber768::CIPHER_TEXT_BYTES }>().to_vec(),
+        shared_secret: ss.to_vec(),
+    };
+    ss.zeroize();
+    Ok(encapsulated)
+}
+
+/// Decapsulates the secret from the cipher text using the key pair `generate` gives.
+///
+/// # Errors
+///
+/// will return an error if the key pair or the cipher text is malformed
+#[wasm_bindgen]
+pub fn decapsulate(key_pair: &[u8], cipher_text: &[u8]) -> Result<Vec<u8>, JsError> {
+    let pair = KeyPair::<DIM>::try_from_bytes(key_pair)?;
+    let ct = CipherText::<DIM>::try_from_bytes(cipher_text)?;
+    let mut ss = pair.decapsulate(&ct);
+    let v = ss.to_vec();
+    ss.zeroize();
+    Ok(v)
+}
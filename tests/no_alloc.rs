@@ -0,0 +1,53 @@
+// The key generation, encapsulation and decapsulation never touch the heap. The crate is
+// `no_std` without `alloc`, this checks the dependencies at run time as well, the build
+// for a target without `std` is `check_no_std.sh`. A separate binary, so nothing else
+// allocates concurrently. The `rayon` feature allocates in the thread pool.
+#![cfg(not(feature = "rayon"))]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use vru_kyber::kem::{KeySeed, key_pair, encapsulate, decapsulate, PublicKey, CipherText};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn round_trip<const DIM: usize, const PK: usize, const CT: usize>()
+where
+    vru_kyber::config::Dim<DIM>: vru_kyber::config::Config<32>,
+{
+    let (sk, pk) = key_pair::<DIM>(KeySeed {
+        main: [1; 32],
+        reject: [2; 32],
+    });
+    let pk = PublicKey::<DIM>::from_bytes(&pk.to_array::<PK>());
+    let (ct, ss) = encapsulate([3; 32], &pk);
+    let ct = CipherText::<DIM>::from_bytes(&ct.to_array::<CT>());
+    assert!(decapsulate(&sk, &pk, &ct) == ss);
+}
+
+#[test]
+fn no_alloc() {
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    round_trip::<2, 800, 768>();
+    round_trip::<3, 1184, 1088>();
+    round_trip::<4, 1568, 1568>();
+    assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), before);
+}
@@ -0,0 +1,32 @@
+// Run with `CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner cargo test --target wasm32-unknown-unknown --features wasm --test wasm`
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vru_kyber::{
+    kem::Kyber768,
+    wasm::{generate, public_key, encapsulate, decapsulate},
+};
+
+#[wasm_bindgen_test]
+fn round_trip() {
+    let key_pair = generate();
+    assert_eq!(key_pair.len(), Kyber768::SECRET_KEY_BYTES);
+    let pk = public_key(&key_pair).unwrap();
+    assert_eq!(pk.len(), Kyber768::PUBLIC_KEY_BYTES);
+
+    let encapsulated = encapsulate(&pk).unwrap();
+    assert_eq!(
+        encapsulated.cipher_text().len(),
+        Kyber768::CIPHER_TEXT_BYTES
+    );
+    let ss = decapsulate(&key_pair, &encapsulated.cipher_text()).unwrap();
+    assert_eq!(ss, encapsulated.shared_secret());
+}
+
+#[wasm_bindgen_test]
+fn malformed() {
+    assert!(public_key(&[0; 100]).is_err());
+    assert!(encapsulate(&[0; 100]).is_err());
+    assert!(decapsulate(&generate(), &[0; 100]).is_err());
+}